@@ -0,0 +1,188 @@
+local M = {}
+
+local defaults = {
+  -- Any of "cargo", "npm" and "pypi"
+  ecosystems = { "cargo", "npm", "pypi" },
+  -- Seconds before a registry lookup is refreshed, older results are still
+  -- shown while the refresh happens in the background
+  cache_ttl = 3600,
+  -- Events checking the manifest in the current buffer
+  events = { "BufEnter" },
+  -- One of "virtual_text", "diagnostics" or "both"
+  display = "both",
+  -- Seconds before a registry request is given up
+  timeout = 10,
+  -- How many times a failed request is retried, waiting longer every time
+  -- (or as long as the registry asks to, when rate limited)
+  retries = 3,
+  -- Path of a PEM bundle with extra root certificates, for networks with TLS inspection
+  ca_bundle = nil,
+  -- Contact information sent in the User-Agent, next to the plugin version
+  user_agent = nil,
+  -- Alternate registry per ecosystem, either its url or a table with the
+  -- url and a token, e.g. { npm = { url = "https://npm.example.com", token = "..." } }
+  registries = {},
+  -- Local clone of https://github.com/rustsec/advisory-db, defaults to the one
+  -- kept by cargo-audit in ~/.cargo/advisory-db
+  advisory_db = nil,
+  -- OSV databases for npm and PyPI packages, directories of json files or zips of them
+  osv_db = {},
+  -- SPDX ids or patterns like "GPL-*" of the licenses dependencies may use, and may not use.
+  -- Anything is allowed when the allow list is empty, denied licenses never are
+  license_policy = { allow = {}, deny = {} },
+  -- Days without any release before a dependency is flagged as abandoned, 0 to never flag them
+  abandoned_after = 3 * 365,
+  -- prefix, separator and fields are also accepted, falling back
+  -- to the g:package_info_* variables when not set
+}
+
+local manifests = {
+  cargo = { pattern = "*Cargo.toml", message = "cargo-toml" },
+  npm = { pattern = "*package.json", message = "package-json" },
+  pypi = { pattern = "*Pipfile", message = "pipfile" },
+}
+
+local highlights = {
+  PackageInfoUpToDate = "Comment",
+  PackageInfoPatch = "String",
+  PackageInfoMinor = "Directory",
+  PackageInfoMajor = "Error",
+  PackageInfoMismatch = "Error",
+  PackageInfoError = "Comment",
+  PackageInfoNotFound = "WarningMsg",
+  PackageInfoRateLimited = "Todo",
+  PackageInfoParseError = "ErrorMsg",
+  PackageInfoAdvisory = "DiagnosticError",
+  PackageInfoCompatible = "WarningMsg",
+  PackageInfoBehind = "Comment",
+  PackageInfoAge = "Comment",
+  PackageInfoAbandoned = "DiagnosticWarn",
+  PackageInfoLicense = "Comment",
+  PackageInfoLicenseViolation = "DiagnosticError",
+  PackageInfoNotInstalled = "WarningMsg",
+  PackageInfoDrift = "DiagnosticWarn",
+}
+
+-- The binary is downloaded by install.sh in the plugin directory
+local root = vim.fn.fnamemodify(debug.getinfo(1, "S").source:sub(2), ":p:h:h")
+local bin = root .. "/plugin/neovim-package-info"
+
+local job = 0
+
+M.config = nil
+
+local function start()
+  job = vim.fn.jobstart({ bin }, {
+    rpc = true,
+    on_exit = function()
+      job = 0
+    end,
+  })
+  if job == 0 then
+    vim.notify("neovim-package-info: cannot start rpc process", vim.log.levels.ERROR)
+  elseif job == -1 then
+    vim.notify("neovim-package-info: rpc process is not executable", vim.log.levels.ERROR)
+  else
+    vim.rpcnotify(job, "configure", M.config)
+  end
+end
+
+-- Send a message to the process, (re)starting it if it is not running
+function M.send(...)
+  if job <= 0 then
+    start()
+  end
+  if job <= 0 then
+    return
+  end
+  if not pcall(vim.rpcnotify, job, ...) then
+    -- The process died before on_exit got the chance to run
+    start()
+    if job > 0 then
+      vim.rpcnotify(job, ...)
+    end
+  end
+end
+
+local function current()
+  return vim.fn.expand("%:p"), vim.fn.bufnr("%")
+end
+
+-- Full paths of all the manifests loaded in the editor
+local function manifest_paths()
+  local paths = {}
+  for _, buffer in ipairs(vim.fn.getbufinfo({ buflisted = 1 })) do
+    if buffer.loaded == 1 then
+      local path = vim.fn.fnamemodify(buffer.name, ":p")
+      for _, manifest in pairs(manifests) do
+        if vim.fn.fnamemodify(path, ":t") == manifest.pattern:sub(2) then
+          table.insert(paths, path)
+        end
+      end
+    end
+  end
+  return paths
+end
+
+local function set_highlights()
+  for group, link in pairs(highlights) do
+    vim.cmd(string.format("highlight default link %s %s", group, link))
+  end
+end
+
+local function set_autocommands()
+  local group = vim.api.nvim_create_augroup("packageInfo", { clear = true })
+  vim.api.nvim_create_autocmd("ColorScheme", { group = group, callback = set_highlights })
+  for _, ecosystem in ipairs(M.config.ecosystems) do
+    local manifest = manifests[ecosystem]
+    if manifest then
+      vim.api.nvim_create_autocmd(M.config.events, {
+        group = group,
+        pattern = manifest.pattern,
+        callback = function()
+          M.send(manifest.message, current())
+        end,
+      })
+    end
+  end
+end
+
+local function set_commands()
+  vim.api.nvim_create_user_command("PackageInfoUpgradeAll", function(opts)
+    local path, bufnr = current()
+    M.send("upgrade-all", path, bufnr, opts.args ~= "" and opts.args or "minor")
+  end, {
+    nargs = "?",
+    complete = function()
+      return { "patch", "minor", "major" }
+    end,
+  })
+  vim.api.nvim_create_user_command("PackageInfoOutdated", function()
+    M.send("outdated", current())
+  end, {})
+  vim.api.nvim_create_user_command("PackageInfoOutdatedAll", function()
+    local path, bufnr = current()
+    M.send("outdated-all", path, bufnr, manifest_paths())
+  end, {})
+  vim.api.nvim_create_user_command("PackageInfoHover", function()
+    local path, bufnr = current()
+    M.send("hover", path, bufnr, vim.fn.line(".") - 1)
+  end, {})
+  vim.api.nvim_create_user_command("PackageInfoChangelog", function()
+    local path, bufnr = current()
+    M.send("changelog", path, bufnr, vim.fn.line(".") - 1)
+  end, {})
+end
+
+function M.setup(opts)
+  M.config = vim.tbl_extend("force", defaults, opts or {})
+  set_highlights()
+  set_autocommands()
+  set_commands()
+  -- Forward the new options if the process is already running
+  if job > 0 then
+    M.send("configure", M.config)
+  end
+end
+
+return M
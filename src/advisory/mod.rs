@@ -0,0 +1,94 @@
+mod cvss;
+mod osv;
+mod rustsec;
+
+use crate::neovim::Config;
+use std::fmt;
+
+// Ordered, so the highest severity of a dependency is the max of its advisories
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Unknown,
+    Low,
+    Moderate,
+    High,
+    Critical,
+}
+
+impl Severity {
+    // Labels used by the GitHub advisories, among others
+    fn from_label(label: &str) -> Self {
+        match &label.to_lowercase()[..] {
+            "low" => Severity::Low,
+            "moderate" | "medium" => Severity::Moderate,
+            "high" => Severity::High,
+            "critical" => Severity::Critical,
+            _ => Severity::Unknown,
+        }
+    }
+
+    fn from_score(score: f64) -> Self {
+        if score >= 9.0 {
+            Severity::Critical
+        } else if score >= 7.0 {
+            Severity::High
+        } else if score >= 4.0 {
+            Severity::Moderate
+        } else if score > 0.0 {
+            Severity::Low
+        } else {
+            Severity::Unknown
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Unknown => write!(f, "unknown"),
+            Severity::Low => write!(f, "low"),
+            Severity::Moderate => write!(f, "moderate"),
+            Severity::High => write!(f, "high"),
+            Severity::Critical => write!(f, "critical"),
+        }
+    }
+}
+
+// A known vulnerability, or an informational notice, affecting a locked version
+#[derive(Clone)]
+pub struct Advisory {
+    pub id: String,
+    // Other ids of the same advisory, like its CVE
+    pub aliases: Vec<String>,
+    pub title: String,
+    pub date: String,
+    pub url: Option<String>,
+    pub severity: Severity,
+    // Requirements matched by the versions fixing it
+    pub patched: Vec<String>,
+    // Kind of notice (unmaintained, unsound...) when it's not a vulnerability
+    pub informational: Option<String>,
+}
+
+/* Advisories affecting the locked version of a dependency, read from the local databases.
+ * The most severe come first.
+ */
+pub fn get_advisories(
+    ecosystem: &str,
+    name: &str,
+    version: &str,
+    config: &Config,
+) -> Vec<Advisory> {
+    // Nothing is installed, nothing can be affected
+    if version.is_empty() {
+        return vec![];
+    }
+    let mut advisories = match ecosystem {
+        "cargo" => rustsec::get_advisories(config.advisory_db.as_deref(), name, version),
+        "npm" => osv::get_advisories(&config.osv_db, "npm", name, version),
+        "pypi" => osv::get_advisories(&config.osv_db, "PyPI", name, version),
+        _ => vec![],
+    };
+    advisories.sort_by(|a, b| b.severity.cmp(&a.severity).then_with(|| a.id.cmp(&b.id)));
+    advisories
+}
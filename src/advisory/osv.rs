@@ -0,0 +1,311 @@
+use crate::advisory::{cvss, Advisory, Severity};
+use crate::python;
+
+use serde::Deserialize;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::SystemTime;
+
+#[derive(Deserialize)]
+struct Record {
+    id: String,
+    #[serde(default)]
+    aliases: Vec<String>,
+    #[serde(default)]
+    summary: String,
+    #[serde(default)]
+    details: String,
+    #[serde(default)]
+    published: String,
+    withdrawn: Option<String>,
+    #[serde(default)]
+    severity: Vec<Score>,
+    #[serde(default)]
+    affected: Vec<Affected>,
+    #[serde(default)]
+    references: Vec<Reference>,
+    #[serde(default)]
+    database_specific: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+struct Score {
+    #[serde(rename = "type")]
+    kind: String,
+    score: String,
+}
+
+#[derive(Deserialize)]
+struct Affected {
+    package: Option<Package>,
+    #[serde(default)]
+    ranges: Vec<Range>,
+    #[serde(default)]
+    versions: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct Package {
+    ecosystem: String,
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct Range {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    events: Vec<HashMap<String, String>>,
+}
+
+#[derive(Deserialize)]
+struct Reference {
+    #[serde(rename = "type")]
+    kind: String,
+    url: String,
+}
+
+// The versions of a package an advisory applies to
+struct Entry {
+    advisory: Advisory,
+    // Events of each range, as (kind, version) pairs
+    ranges: Vec<Vec<(String, String)>>,
+    versions: Vec<String>,
+}
+
+// Entries by ecosystem and package name
+type Index = HashMap<(String, String), Vec<Entry>>;
+
+// An index along with the modification time of the database it was loaded from
+type Loaded = (Option<SystemTime>, Arc<Index>);
+
+// Databases are big, they are loaded once and again only when they change on disk
+static INDEXES: OnceLock<Mutex<HashMap<PathBuf, Loaded>>> = OnceLock::new();
+
+// PyPI names are compared normalized, names are exact everywhere else
+fn package_key(ecosystem: &str, name: &str) -> (String, String) {
+    match ecosystem {
+        "PyPI" => (ecosystem.to_string(), python::normalize_name(name)),
+        _ => (ecosystem.to_string(), name.to_string()),
+    }
+}
+
+// Compare versions with the semantics of the ecosystem, None if either can't be parsed
+fn compare(ecosystem: &str, a: &str, b: &str) -> Option<Ordering> {
+    match ecosystem {
+        "PyPI" => Some(python::Version::parse(a)?.cmp(&python::Version::parse(b)?)),
+        _ => {
+            let a = semver::Version::parse(a).ok()?;
+            Some(a.cmp(&semver::Version::parse(b).ok()?))
+        }
+    }
+}
+
+fn severity(record: &Record) -> Severity {
+    // A label when the database gives one, GitHub advisories do
+    if let Some(label) = record.database_specific["severity"].as_str() {
+        let severity = Severity::from_label(label);
+        if severity != Severity::Unknown {
+            return severity;
+        }
+    }
+    record
+        .severity
+        .iter()
+        .filter(|score| score.kind == "CVSS_V3")
+        .filter_map(|score| cvss::base_score(&score.score))
+        .map(Severity::from_score)
+        .max()
+        .unwrap_or(Severity::Unknown)
+}
+
+fn index_record(index: &mut Index, record: Record) {
+    if record.withdrawn.is_some() {
+        return;
+    }
+    let title = match record.summary.trim() {
+        "" => record
+            .details
+            .lines()
+            .next()
+            .unwrap_or(&record.id)
+            .to_string(),
+        summary => summary.to_string(),
+    };
+    let url = record
+        .references
+        .iter()
+        .find(|reference| reference.kind == "ADVISORY")
+        .or_else(|| record.references.first())
+        .map(|reference| reference.url.clone());
+    let advisory = Advisory {
+        severity: severity(&record),
+        id: record.id,
+        aliases: record.aliases,
+        title,
+        date: record.published.chars().take(10).collect(),
+        url,
+        patched: vec![],
+        informational: None,
+    };
+    for affected in record.affected {
+        let package = match affected.package {
+            Some(package) => package,
+            None => continue,
+        };
+        // Git ranges are commits, not versions
+        let ranges: Vec<Vec<(String, String)>> = affected
+            .ranges
+            .into_iter()
+            .filter(|range| range.kind == "SEMVER" || range.kind == "ECOSYSTEM")
+            .map(|range| range.events.into_iter().flatten().collect())
+            .collect();
+        let patched = ranges
+            .iter()
+            .flatten()
+            .filter(|(kind, _)| kind == "fixed")
+            .map(|(_, fixed)| format!(">= {}", fixed))
+            .collect();
+        index
+            .entry(package_key(&package.ecosystem, &package.name))
+            .or_default()
+            .push(Entry {
+                advisory: Advisory {
+                    patched,
+                    ..advisory.clone()
+                },
+                ranges,
+                versions: affected.versions,
+            });
+    }
+}
+
+fn add_json(index: &mut Index, content: &str) {
+    // Skip what isn't an advisory, rather than failing the whole database
+    if let Ok(record) = serde_json::from_str::<Record>(content) {
+        index_record(index, record);
+    }
+}
+
+fn load_dir(index: &mut Index, dir: &Path) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for path in entries.filter_map(|entry| Some(entry.ok()?.path())) {
+        if path.is_dir() {
+            load_dir(index, &path);
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            if let Ok(content) = fs::read_to_string(&path) {
+                add_json(index, &content);
+            }
+        }
+    }
+}
+
+fn load_zip(index: &mut Index, path: &Path) -> Option<()> {
+    let mut archive = zip::ZipArchive::new(File::open(path).ok()?).ok()?;
+    for i in 0..archive.len() {
+        let mut file = match archive.by_index(i) {
+            Ok(file) => file,
+            Err(_) => continue,
+        };
+        if !file.name().ends_with(".json") {
+            continue;
+        }
+        let mut content = String::new();
+        if file.read_to_string(&mut content).is_ok() {
+            add_json(index, &content);
+        }
+    }
+    Some(())
+}
+
+// The database at the path, a directory of OSV json files or a zip of them
+fn get_index(path: &Path) -> Arc<Index> {
+    let modified = fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok();
+    let indexes = INDEXES.get_or_init(|| Mutex::new(HashMap::new()));
+    let mut indexes = indexes.lock().unwrap();
+    if let Some((loaded, index)) = indexes.get(path) {
+        if *loaded == modified {
+            return index.clone();
+        }
+    }
+    let mut index = Index::new();
+    if path.is_dir() {
+        load_dir(&mut index, path);
+    } else {
+        load_zip(&mut index, path);
+    }
+    let index = Arc::new(index);
+    indexes.insert(path.to_path_buf(), (modified, index.clone()));
+    index
+}
+
+/* Walk the events in version order: the version is affected after an introduced event
+ * it's not lower than, until a fixed event it's not lower than, or a last_affected
+ * event it's higher than
+ */
+fn in_range(ecosystem: &str, events: &[(String, String)], version: &str) -> bool {
+    let mut events: Vec<&(String, String)> = events
+        .iter()
+        .filter(|(_, event)| event == "0" || compare(ecosystem, event, event).is_some())
+        .collect();
+    events.sort_by(|(_, a), (_, b)| match (&a[..], &b[..]) {
+        ("0", "0") => Ordering::Equal,
+        ("0", _) => Ordering::Less,
+        (_, "0") => Ordering::Greater,
+        (a, b) => compare(ecosystem, a, b).unwrap_or(Ordering::Equal),
+    });
+    let mut affected = false;
+    for (kind, event) in events {
+        let ordering = match &event[..] {
+            "0" => Ordering::Greater,
+            event => match compare(ecosystem, version, event) {
+                Some(ordering) => ordering,
+                None => return false,
+            },
+        };
+        match &kind[..] {
+            "introduced" if ordering != Ordering::Less => affected = true,
+            "fixed" if ordering != Ordering::Less => affected = false,
+            "last_affected" if ordering == Ordering::Greater => affected = false,
+            _ => (),
+        }
+    }
+    affected
+}
+
+pub fn get_advisories(
+    databases: &[String],
+    ecosystem: &str,
+    name: &str,
+    version: &str,
+) -> Vec<Advisory> {
+    let key = package_key(ecosystem, name);
+    let mut advisories: Vec<Advisory> = vec![];
+    for database in databases {
+        let index = get_index(Path::new(database));
+        for entry in index.get(&key).into_iter().flatten() {
+            let affected = entry.versions.iter().any(|affected| affected == version)
+                || entry
+                    .ranges
+                    .iter()
+                    .any(|events| in_range(ecosystem, events, version));
+            // The same advisory can be in more than one database
+            if affected && !advisories.iter().any(|a| a.id == entry.advisory.id) {
+                advisories.push(entry.advisory.clone());
+            }
+        }
+    }
+    advisories
+}
@@ -0,0 +1,113 @@
+use crate::advisory::{cvss, Advisory, Severity};
+use crate::store::cargo_home;
+
+use semver::{Version, VersionReq};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Deserialize)]
+struct Metadata {
+    advisory: AdvisoryMetadata,
+    #[serde(default)]
+    versions: Versions,
+}
+
+#[derive(Deserialize)]
+struct AdvisoryMetadata {
+    id: String,
+    #[serde(default)]
+    aliases: Vec<String>,
+    #[serde(default)]
+    title: String,
+    date: String,
+    url: Option<String>,
+    cvss: Option<String>,
+    informational: Option<String>,
+    withdrawn: Option<String>,
+    // Older advisories keep the versions here, instead of a [versions] table
+    #[serde(default)]
+    patched_versions: Vec<String>,
+    #[serde(default)]
+    unaffected_versions: Vec<String>,
+}
+
+#[derive(Default, Deserialize)]
+struct Versions {
+    #[serde(default)]
+    patched: Vec<String>,
+    #[serde(default)]
+    unaffected: Vec<String>,
+}
+
+// The checkout cargo-audit keeps up to date, when no path is configured
+fn default_path() -> Option<PathBuf> {
+    Some(cargo_home()?.join("advisory-db"))
+}
+
+/* Advisories are markdown files with a TOML front matter in a ```toml block,
+ * and the title as first heading. Older ones are plain TOML files.
+ */
+fn parse(path: &Path) -> Option<Metadata> {
+    let content = fs::read_to_string(path).ok()?;
+    if path.extension()? == "toml" {
+        return toml::from_str(&content).ok();
+    }
+    let start = content.find("```toml")? + "```toml".len();
+    let end = start + content[start..].find("```")?;
+    let mut metadata: Metadata = toml::from_str(&content[start..end]).ok()?;
+    if let Some(title) = content[end..].lines().find(|line| line.starts_with("# ")) {
+        metadata.advisory.title = title[2..].trim().to_string();
+    }
+    Some(metadata)
+}
+
+// Unparseable requirements are ignored, rather than taken as matching
+fn matches_any(requirements: &[String], version: &Version) -> bool {
+    requirements
+        .iter()
+        .filter_map(|requirement| VersionReq::parse(requirement).ok())
+        .any(|requirement| requirement.matches(version))
+}
+
+pub fn get_advisories(database: Option<&str>, name: &str, version: &str) -> Vec<Advisory> {
+    let version = match Version::parse(version) {
+        Ok(version) => version,
+        Err(_) => return vec![],
+    };
+    let database = match database.map(PathBuf::from).or_else(default_path) {
+        Some(database) => database,
+        None => return vec![],
+    };
+    let entries = match fs::read_dir(database.join("crates").join(name)) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+    entries
+        .filter_map(|entry| parse(&entry.ok()?.path()))
+        .filter(|metadata| metadata.advisory.withdrawn.is_none())
+        .filter_map(|metadata| {
+            let Metadata { advisory, versions } = metadata;
+            let patched = [versions.patched, advisory.patched_versions].concat();
+            let unaffected = [versions.unaffected, advisory.unaffected_versions].concat();
+            if matches_any(&patched, &version) || matches_any(&unaffected, &version) {
+                return None;
+            }
+            let severity = advisory
+                .cvss
+                .as_deref()
+                .and_then(cvss::base_score)
+                .map_or(Severity::Unknown, Severity::from_score);
+            Some(Advisory {
+                id: advisory.id,
+                aliases: advisory.aliases,
+                title: advisory.title,
+                date: advisory.date,
+                url: advisory.url,
+                severity,
+                patched,
+                informational: advisory.informational,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,207 @@
+use crate::store::cargo_home;
+
+use flate2::read::GzDecoder;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+// Names changelogs are usually kept under, the first found wins
+static NAMES: [&str; 5] = ["changelog", "changes", "history", "releases", "news"];
+static EXTENSIONS: [&str; 5] = ["md", "markdown", "rst", "txt", ""];
+
+// Preference of a file name as a changelog, lower is better
+fn rank(file_name: &str) -> Option<usize> {
+    let file_name = file_name.to_lowercase();
+    let (stem, extension) = file_name.split_once('.').unwrap_or((&file_name, ""));
+    let name = NAMES.iter().position(|name| *name == stem)?;
+    let extension = EXTENSIONS.iter().position(|e| *e == extension)?;
+    Some(name * EXTENSIONS.len() + extension)
+}
+
+// The changelog at the top of a package directory
+fn find_in_dir(dir: &Path) -> Option<(PathBuf, String)> {
+    let path = fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| path.is_file())
+        .filter_map(|path| Some((rank(path.file_name()?.to_str()?)?, path)))
+        .min()
+        .map(|(_, path)| path)?;
+    let content = fs::read_to_string(&path).ok()?;
+    Some((path, content))
+}
+
+// Version of the package installed in node_modules
+fn installed_version(package_dir: &Path) -> Option<String> {
+    let manifest = fs::read_to_string(package_dir.join("package.json")).ok()?;
+    let manifest: serde_json::Value = serde_json::from_str(&manifest).ok()?;
+    Some(manifest["version"].as_str()?.to_string())
+}
+
+/* A copy of the version already on disk: the sources cargo extracted in its registry,
+ * or the package installed in node_modules next to the manifest when it's that version
+ */
+pub fn find_vendored(
+    ecosystem: &str,
+    name: &str,
+    version: &str,
+    manifest_dir: &Path,
+) -> Option<(PathBuf, String)> {
+    match ecosystem {
+        "cargo" => {
+            // One directory per registry, like index.crates.io-6f17d22bba15001f
+            fs::read_dir(cargo_home()?.join("registry/src"))
+                .ok()?
+                .filter_map(|entry| Some(entry.ok()?.path()))
+                .find_map(|registry| find_in_dir(&registry.join(format!("{}-{}", name, version))))
+        }
+        "npm" => {
+            let package_dir = manifest_dir.join("node_modules").join(name);
+            if installed_version(&package_dir)? != version {
+                return None;
+            }
+            find_in_dir(&package_dir)
+        }
+        _ => None,
+    }
+}
+
+/* The changelog of the package and where it comes from. Only the latest version has the
+ * notes up to itself: its copy on disk, or else its archive from the registry. The copy
+ * of the current version is a last resort, it at least tells what the package is about
+ */
+pub fn find(
+    ecosystem: &str,
+    name: &str,
+    latest: &str,
+    current: &str,
+    manifest_dir: &Path,
+    get_tarball: impl FnOnce() -> Option<Vec<u8>>,
+) -> Option<(String, String)> {
+    if let Some((path, content)) = find_vendored(ecosystem, name, latest, manifest_dir) {
+        return Some((path.display().to_string(), content));
+    }
+    if let Some(content) = get_tarball().and_then(|tarball| find_in_tarball(&tarball)) {
+        return Some((format!("{} {} archive", name, latest), content));
+    }
+    let (path, content) = find_vendored(ecosystem, name, current, manifest_dir)?;
+    Some((path.display().to_string(), content))
+}
+
+/* The changelog in a .crate or npm .tgz archive, both gzipped tarballs
+ * with the package files in a top directory
+ */
+pub fn find_in_tarball(tarball: &[u8]) -> Option<String> {
+    let mut archive = tar::Archive::new(GzDecoder::new(tarball));
+    let mut best: Option<(usize, String)> = None;
+    for entry in archive.entries().ok()? {
+        let mut entry = match entry {
+            Ok(entry) => entry,
+            Err(_) => continue,
+        };
+        let rank = {
+            let path = match entry.path() {
+                Ok(path) => path,
+                Err(_) => continue,
+            };
+            if path.components().count() != 2 {
+                continue;
+            }
+            match path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(rank)
+            {
+                Some(rank) => rank,
+                None => continue,
+            }
+        };
+        if best.as_ref().is_some_and(|(best, _)| *best <= rank) {
+            continue;
+        }
+        let mut content = String::new();
+        if entry.read_to_string(&mut content).is_ok() {
+            best = Some((rank, content));
+        }
+    }
+    best.map(|(_, content)| content)
+}
+
+// A version like 1.2 or 1.2.3-rc.1, padded with zeros to be compared as semver
+fn parse_version(version: &str) -> Option<semver::Version> {
+    let version = version.trim_start_matches(['v', 'V']);
+    if !version.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    let (release, pre) = version.split_once('-').unwrap_or((version, ""));
+    let mut parts: Vec<&str> = release.split('.').collect();
+    if parts.len() < 2 || parts.len() > 3 {
+        return None;
+    }
+    while parts.len() < 3 {
+        parts.push("0");
+    }
+    let version = match pre {
+        "" => parts.join("."),
+        pre => format!("{}-{}", parts.join("."), pre),
+    };
+    semver::Version::parse(&version).ok()
+}
+
+// Versions can be written as v1.2.3, [1.2.3] or 1.2, the first one in the heading is used
+fn heading_version(heading: &str) -> Option<semver::Version> {
+    heading
+        .split(|c: char| c.is_whitespace() || "[]()".contains(c))
+        .find_map(|word| parse_version(word.trim_end_matches([':', ','])))
+}
+
+// Level of a markdown heading, either # prefixed or underlined
+fn heading_level(line: &str, next: Option<&str>) -> Option<usize> {
+    let hashes = line.chars().take_while(|c| *c == '#').count();
+    if hashes > 0 {
+        return Some(hashes);
+    }
+    let next = next?.trim();
+    if line.trim().is_empty() || next.len() < 3 {
+        return None;
+    }
+    if next.chars().all(|c| c == '=') {
+        Some(1)
+    } else if next.chars().all(|c| c == '-') {
+        Some(2)
+    } else {
+        None
+    }
+}
+
+/* The sections of the changelog about the versions after the current one, up to the latest.
+ * Sections start at headings with a version, at the level of the first of them.
+ * None if there are no such headings, in which case the changelog can't be split
+ */
+pub fn sections(changelog: &str, current: &str, latest: &str) -> Option<Vec<String>> {
+    let current = parse_version(current);
+    let latest = parse_version(latest);
+    let lines: Vec<&str> = changelog.lines().collect();
+    let mut level: Option<usize> = None;
+    let mut included = false;
+    let mut sections: Vec<String> = vec![];
+    for (index, line) in lines.iter().enumerate() {
+        if let Some(heading) = heading_level(line, lines.get(index + 1).copied()) {
+            let version = heading_version(line.trim_start_matches('#'));
+            if level.is_none() && version.is_some() {
+                level = Some(heading);
+            }
+            // Deeper headings are part of the section
+            if level.is_some_and(|level| heading <= level) {
+                included = version.is_some_and(|version| {
+                    current.as_ref().is_none_or(|current| version > *current)
+                        && latest.as_ref().is_none_or(|latest| version <= *latest)
+                });
+            }
+        }
+        if included {
+            sections.push(line.to_string());
+        }
+    }
+    level.map(|_| sections)
+}
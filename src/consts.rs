@@ -1,5 +1,86 @@
+use std::time::Duration;
+
 pub static PREFIX: &str = "  ¤ ";
+pub static SEPARATOR: &str = " -> ";
+
+// Per registry
+pub static MAX_CONCURRENT_REQUESTS: usize = 8;
+
+// First wait before retrying a failed request, doubled at every retry
+pub static RETRY_BACKOFF: Duration = Duration::from_millis(500);
+pub static MAX_RETRY_WAIT: Duration = Duration::from_secs(30);
+
+// Highlight groups, linked to builtin groups by default in the plugin
+pub static UP_TO_DATE_HG: &str = "PackageInfoUpToDate";
+pub static PATCH_HG: &str = "PackageInfoPatch";
+pub static MINOR_HG: &str = "PackageInfoMinor";
+pub static MAJOR_HG: &str = "PackageInfoMajor";
+pub static MISMATCH_HG: &str = "PackageInfoMismatch";
+pub static ERROR_HG: &str = "PackageInfoError";
+pub static NOT_FOUND_HG: &str = "PackageInfoNotFound";
+pub static RATE_LIMITED_HG: &str = "PackageInfoRateLimited";
+pub static PARSE_ERROR_HG: &str = "PackageInfoParseError";
+pub static ADVISORY_HG: &str = "PackageInfoAdvisory";
+pub static COMPATIBLE_HG: &str = "PackageInfoCompatible";
+pub static BEHIND_HG: &str = "PackageInfoBehind";
+pub static AGE_HG: &str = "PackageInfoAge";
+pub static ABANDONED_HG: &str = "PackageInfoAbandoned";
+pub static LICENSE_HG: &str = "PackageInfoLicense";
+pub static LICENSE_VIOLATION_HG: &str = "PackageInfoLicenseViolation";
+pub static NOT_INSTALLED_HG: &str = "PackageInfoNotInstalled";
+pub static DRIFT_HG: &str = "PackageInfoDrift";
+
+// Shown instead of the version of dependencies missing from the lockfile and not installed
+pub static NOT_INSTALLED: &str = "not installed";
+// Shown instead of the version of dependencies the lockfile has no entry for, and before the lockfile banner
+pub static UNLOCKED_MARKER: &str = "≠ not locked";
+pub static DRIFT_MARKER: &str = "≠ ";
+
+// Shown before the ids of the advisories affecting a dependency
+pub static ADVISORY_MARKER: &str = " ⚠ ";
+// How many advisory ids are shown in the virtual text
+pub static MAX_ADVISORY_IDS: usize = 3;
+
+// Receives the buffer number and the list of diagnostics, does nothing on Neovim < 0.6
+pub static SET_DIAGNOSTICS_LUA: &str = r#"
+local bufnr, diagnostics = ...
+if vim.diagnostic == nil then
+    return
+end
+local namespace = vim.api.nvim_create_namespace("package-info")
+vim.diagnostic.set(namespace, bufnr, diagnostics)
+"#;
+
+// Receives the markdown lines, shown in a floating window next to the cursor
+pub static HOVER_LUA: &str = r#"
+local lines = ...
+vim.lsp.util.open_floating_preview(lines, "markdown", { focus_id = "package-info" })
+"#;
+
+// Receives the markdown lines, opened in a scratch buffer in a new split
+pub static SCRATCH_LUA: &str = r#"
+local lines = ...
+local buf = vim.api.nvim_create_buf(false, true)
+vim.api.nvim_buf_set_lines(buf, 0, -1, false, lines)
+vim.bo[buf].filetype = "markdown"
+vim.bo[buf].bufhidden = "wipe"
+vim.bo[buf].modifiable = false
+vim.cmd("split")
+vim.api.nvim_win_set_buf(0, buf)
+"#;
 
-pub static BLUE_HG: &str = "Directory";
-pub static GREY_HG: &str = "Comment";
-pub static RED_HG: &str = "Error";
+// Receives the buffer number, the banner lines and their highlight, shown below the first line
+pub static BANNER_LUA: &str = r#"
+local bufnr, lines, highlight = ...
+local namespace = vim.api.nvim_create_namespace("package-info-banner")
+vim.api.nvim_buf_clear_namespace(bufnr, namespace, 0, -1)
+if #lines == 0 then
+    return
+end
+local virt_lines = {}
+for _, line in ipairs(lines) do
+    table.insert(virt_lines, { { line, highlight } })
+end
+-- Below the first line, lines above it are hidden until the window is scrolled up past the top
+vim.api.nvim_buf_set_extmark(bufnr, namespace, 0, 0, { virt_lines = virt_lines })
+"#;
@@ -0,0 +1,9 @@
+pub mod advisory;
+pub mod changelog;
+pub mod consts;
+pub mod license;
+pub mod neovim;
+pub mod parser;
+pub mod python;
+pub mod store;
+pub mod upgrade;
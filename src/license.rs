@@ -0,0 +1,203 @@
+// A parsed SPDX license expression, see https://spdx.github.io/spdx-spec/v2.3/SPDX-license-expressions/
+#[derive(Debug, PartialEq)]
+pub enum Expression {
+    // A license id, with its exception if any
+    License(String, Option<String>),
+    And(Box<Expression>, Box<Expression>),
+    Or(Box<Expression>, Box<Expression>),
+}
+
+// Split the expression in ids, operators and parentheses
+fn tokenize(expression: &str) -> Vec<String> {
+    expression
+        .replace('(', " ( ")
+        .replace(')', " ) ")
+        // Old crates use a slash for OR
+        .replace('/', " OR ")
+        .split_whitespace()
+        .map(|token| token.to_string())
+        .collect()
+}
+
+struct ExpressionParser {
+    tokens: Vec<String>,
+    position: usize,
+}
+
+impl ExpressionParser {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.position).map(|token| token.as_str())
+    }
+
+    // Consume the next token if it's the given operator, in any case
+    fn accept(&mut self, operator: &str) -> bool {
+        if self
+            .peek()
+            .is_some_and(|token| token.eq_ignore_ascii_case(operator))
+        {
+            self.position += 1;
+            return true;
+        }
+        false
+    }
+
+    // AND binds tighter than OR
+    fn or(&mut self) -> Option<Expression> {
+        let mut expression = self.and()?;
+        while self.accept("OR") {
+            expression = Expression::Or(Box::new(expression), Box::new(self.and()?));
+        }
+        Some(expression)
+    }
+
+    fn and(&mut self) -> Option<Expression> {
+        let mut expression = self.license()?;
+        while self.accept("AND") {
+            expression = Expression::And(Box::new(expression), Box::new(self.license()?));
+        }
+        Some(expression)
+    }
+
+    fn license(&mut self) -> Option<Expression> {
+        if self.accept("(") {
+            let expression = self.or()?;
+            return if self.accept(")") {
+                Some(expression)
+            } else {
+                None
+            };
+        }
+        let id = self.peek()?.to_string();
+        if ["(", ")", "AND", "OR", "WITH"].contains(&id.to_uppercase().as_str()) {
+            return None;
+        }
+        self.position += 1;
+        let exception = if self.accept("WITH") {
+            let exception = self.peek()?.to_string();
+            self.position += 1;
+            Some(exception)
+        } else {
+            None
+        };
+        Some(Expression::License(id, exception))
+    }
+}
+
+impl Expression {
+    pub fn parse(expression: &str) -> Option<Self> {
+        let mut parser = ExpressionParser {
+            tokens: tokenize(expression),
+            position: 0,
+        };
+        let parsed = parser.or()?;
+        if parser.position == parser.tokens.len() {
+            Some(parsed)
+        } else {
+            None
+        }
+    }
+
+    /* Whether a choice of licenses satisfying the expression passes the check:
+     * any side of an OR will do, both sides of an AND must pass
+     */
+    fn satisfies(&self, check: &dyn Fn(&str, Option<&str>) -> bool) -> bool {
+        match self {
+            Expression::License(id, exception) => check(id, exception.as_deref()),
+            Expression::And(left, right) => left.satisfies(check) && right.satisfies(check),
+            Expression::Or(left, right) => left.satisfies(check) || right.satisfies(check),
+        }
+    }
+
+    // Every license id in the expression, in order
+    fn licenses(&self) -> Vec<(&str, Option<&str>)> {
+        match self {
+            Expression::License(id, exception) => vec![(id, exception.as_deref())],
+            Expression::And(left, right) | Expression::Or(left, right) => {
+                let mut licenses = left.licenses();
+                licenses.append(&mut right.licenses());
+                licenses
+            }
+        }
+    }
+}
+
+// Case insensitive match of a license id against a pattern, where * matches anything
+fn glob(pattern: &str, id: &str) -> bool {
+    fn matches(pattern: &[char], id: &[char]) -> bool {
+        match pattern.split_first() {
+            None => id.is_empty(),
+            Some(('*', rest)) => (0..=id.len()).any(|start| matches(rest, &id[start..])),
+            Some((c, rest)) => id.first() == Some(c) && matches(rest, &id[1..]),
+        }
+    }
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let id: Vec<char> = id.to_lowercase().chars().collect();
+    matches(&pattern, &id)
+}
+
+/* Licenses allowed and denied, as SPDX ids or patterns like GPL-*.
+ * A license is accepted when it's not denied, and allowed if there is an allow list
+ */
+#[derive(Clone, Default)]
+pub struct Policy {
+    pub allow: Vec<String>,
+    pub deny: Vec<String>,
+}
+
+impl Policy {
+    pub fn is_empty(&self) -> bool {
+        self.allow.is_empty() && self.deny.is_empty()
+    }
+
+    // A pattern matches the id, with or without its exception, or the or-later suffix
+    fn matches(patterns: &[String], id: &str, exception: Option<&str>) -> bool {
+        let with_exception = exception.map(|exception| format!("{} WITH {}", id, exception));
+        patterns.iter().any(|pattern| {
+            glob(pattern, id)
+                || glob(pattern, id.trim_end_matches('+'))
+                || with_exception
+                    .as_ref()
+                    .is_some_and(|with_exception| glob(pattern, with_exception))
+        })
+    }
+
+    fn denied(&self, id: &str, exception: Option<&str>) -> bool {
+        Self::matches(&self.deny, id, exception)
+    }
+
+    fn accepted(&self, id: &str, exception: Option<&str>) -> bool {
+        !self.denied(id, exception)
+            && (self.allow.is_empty() || Self::matches(&self.allow, id, exception))
+    }
+
+    /* Why the license breaks the policy, if it does. Unknown licenses only do when
+     * there is an allow list, and licenses that aren't valid expressions are
+     * checked as a single id.
+     */
+    pub fn violation(&self, license: Option<&str>) -> Option<String> {
+        if self.is_empty() {
+            return None;
+        }
+        let license = match license {
+            Some(license) => license,
+            None if self.allow.is_empty() => return None,
+            None => return Some("unknown license".to_string()),
+        };
+        let expression = Expression::parse(license)
+            .unwrap_or_else(|| Expression::License(license.to_string(), None));
+        if expression.satisfies(&|id, exception| self.accepted(id, exception)) {
+            return None;
+        }
+        let denied: Vec<&str> = expression
+            .licenses()
+            .into_iter()
+            .filter(|(id, exception)| self.denied(id, *exception))
+            .map(|(id, _)| id)
+            .collect();
+        Some(if denied.is_empty() {
+            format!("license {} is not allowed", license)
+        } else {
+            format!("license {} is denied ({})", license, denied.join(", "))
+        })
+    }
+}
@@ -1,9 +1,5 @@
-mod consts;
-mod neovim;
-mod parser;
-mod store;
-
 use failure::Error;
+use neovim_package_info::neovim;
 
 fn main() -> Result<(), Error> {
     // Just start the event handler and let it go
@@ -1,46 +1,110 @@
-use super::DependencyInfo;
+use crate::store::Package;
 
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
+struct Entry {
+    package: Package,
+    inserted: Instant,
+    // A background refresh is already on its way
+    refreshing: bool,
+}
+
+/* Registry metadata, keyed by the cache key of the store and package name so that packages
+ * with the same name in different registries don't clash.
+ * Entries are served even when older than the duration: in that case the caller
+ * is told to refresh the entry, which it can do in the background.
+ * Clones share the same entries, so refreshes can happen on another thread.
+ */
+#[derive(Clone)]
 pub struct Cache {
-    map: HashMap<String, Vec<(String, String)>>,
-    last_updated: Instant,
+    map: Arc<Mutex<HashMap<(String, String), Entry>>>,
     duration: Duration,
 }
 
 impl Cache {
     pub fn new(duration: u64) -> Self {
         Cache {
-            map: HashMap::new(),
-            last_updated: Instant::now(),
+            map: Arc::new(Mutex::new(HashMap::new())),
             duration: Duration::from_secs(duration),
         }
     }
 
-    pub fn update(&mut self, dependencies: &Vec<DependencyInfo>) {
-        if self.last_updated.elapsed() > self.duration {
-            self.map = HashMap::new();
-        } else {
-            for dep in dependencies {
-                self.insert(&dep);
-            }
+    pub fn set_duration(&mut self, duration: u64) {
+        self.duration = Duration::from_secs(duration);
+    }
+
+    pub fn insert(&self, registry: &str, name: &str, package: Package) {
+        let entry = Entry {
+            package,
+            inserted: Instant::now(),
+            refreshing: false,
+        };
+        let key = (registry.to_string(), name.to_string());
+        self.map.lock().unwrap().insert(key, entry);
+    }
+
+    // Return the cached package, and whether the caller should refresh it
+    pub fn get(&self, registry: &str, name: &str) -> Option<(Package, bool)> {
+        let mut map = self.map.lock().unwrap();
+        let entry = map.get_mut(&(registry.to_string(), name.to_string()))?;
+        let refresh = !entry.refreshing && entry.inserted.elapsed() > self.duration;
+        if refresh {
+            entry.refreshing = true;
         }
-        self.last_updated = Instant::now();
+        Some((entry.package.clone(), refresh))
     }
 
-    pub fn insert(&mut self, dep: &DependencyInfo) {
-        self.map.insert(dep.name.clone(), dep.latest.clone());
+    // The background refresh didn't go through, let the next lookup try again
+    pub fn refresh_failed(&self, registry: &str, name: &str) {
+        let mut map = self.map.lock().unwrap();
+        if let Some(entry) = map.get_mut(&(registry.to_string(), name.to_string())) {
+            entry.refreshing = false;
+        }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
 
-    pub fn get(
-        &self,
-        dep: &DependencyInfo,
-        check_dependency: &Fn(&DependencyInfo) -> Vec<(String, String)>,
-    ) -> Vec<(String, String)> {
-        match self.map.get(&dep.name) {
-            Some(latest) => latest.clone(),
-            None => check_dependency(&dep),
+    fn package() -> Package {
+        Package {
+            max_version: "1.0.0".to_string(),
+            versions: vec!["1.0.0".to_string()],
+            fetched_at: 0,
+            etag: None,
+            last_modified: None,
+            licenses: HashMap::new(),
+            toolchains: HashMap::new(),
+            released: HashMap::new(),
+            last_release: None,
+            repository: None,
         }
     }
+
+    #[test]
+    fn failed_refresh_is_retried() {
+        let cache = Cache::new(0);
+        cache.insert("https://registry", "serde", package());
+        std::thread::sleep(Duration::from_millis(5));
+
+        // Stale, a single caller is told to refresh it
+        assert_eq!(
+            cache.get("https://registry", "serde").map(|(_, r)| r),
+            Some(true)
+        );
+        assert_eq!(
+            cache.get("https://registry", "serde").map(|(_, r)| r),
+            Some(false)
+        );
+
+        cache.refresh_failed("https://registry", "serde");
+        assert_eq!(
+            cache.get("https://registry", "serde").map(|(_, r)| r),
+            Some(true)
+        );
+    }
 }
@@ -0,0 +1,232 @@
+use super::neovim_session::NeovimSession;
+use crate::consts;
+use crate::license::Policy;
+use crate::store::Registry;
+use neovim_lib::Value;
+use std::collections::HashMap;
+
+// Options that can be set with g:package_info_* variables
+static GLOBALS: [&str; 3] = ["prefix", "separator", "fields"];
+
+/* Options read from the g:package_info_* variables at startup,
+ * and from the configure message sent by the Lua setup() afterwards
+ */
+#[derive(Clone)]
+pub struct Config {
+    pub prefix: String,
+    pub separator: String,
+    // Which of "current", "latest", "behind", "behind_since", "license" and "age"
+    // to show in the virtual text
+    pub fields: Vec<String>,
+    // Any of "cargo", "npm" and "pypi"
+    pub ecosystems: Vec<String>,
+    // Seconds before a registry lookup is refreshed
+    pub cache_ttl: u64,
+    // One of "virtual_text", "diagnostics" or "both"
+    pub display: String,
+    // Seconds before a registry request is given up
+    pub timeout: u64,
+    // How many times a failed registry request is retried
+    pub retries: u64,
+    // Extra root certificates for the registry requests, as a PEM bundle path
+    pub ca_bundle: Option<String>,
+    // Contact information sent along with the plugin version in the User-Agent
+    pub user_agent: Option<String>,
+    // Alternate registry of an ecosystem, instead of the public one
+    pub registries: HashMap<String, Registry>,
+    // Local checkout of the RustSec advisory database
+    pub advisory_db: Option<String>,
+    // OSV databases for npm and PyPI, as directories or zips of json files
+    pub osv_db: Vec<String>,
+    // Licenses dependencies are allowed to use, or not
+    pub license_policy: Policy,
+    // Days without a release before a dependency is considered abandoned, 0 to never
+    pub abandoned_after: u64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            prefix: consts::PREFIX.to_string(),
+            separator: consts::SEPARATOR.to_string(),
+            fields: vec!["current".to_string(), "latest".to_string()],
+            ecosystems: vec!["cargo".to_string(), "npm".to_string(), "pypi".to_string()],
+            cache_ttl: 60 * 60,
+            display: "both".to_string(),
+            timeout: 10,
+            retries: 3,
+            ca_bundle: None,
+            user_agent: None,
+            registries: HashMap::new(),
+            advisory_db: None,
+            osv_db: vec![],
+            license_policy: Policy::default(),
+            abandoned_after: 3 * 365,
+        }
+    }
+}
+
+impl Config {
+    pub fn load(nvim_session: &mut NeovimSession) -> Self {
+        let mut config = Config::default();
+        for key in GLOBALS.iter() {
+            if let Some(value) = nvim_session.get_global(&format!("package_info_{}", key)) {
+                config.set(key, &value);
+            }
+        }
+        config
+    }
+
+    // Apply the options table received from the configure message
+    pub fn configure(&mut self, options: &Value) {
+        if let Some(options) = options.as_map() {
+            for (key, value) in options {
+                if let Some(key) = key.as_str() {
+                    self.set(key, value);
+                }
+            }
+        }
+    }
+
+    // Set a single option, ignoring unknown keys and values of the wrong type
+    fn set(&mut self, key: &str, value: &Value) {
+        match key {
+            "prefix" => {
+                if let Some(prefix) = value.as_str() {
+                    self.prefix = prefix.to_string();
+                }
+            }
+            "separator" => {
+                if let Some(separator) = value.as_str() {
+                    self.separator = separator.to_string();
+                }
+            }
+            "fields" => {
+                if let Some(fields) = Self::strings(value) {
+                    self.fields = fields;
+                }
+            }
+            "ecosystems" => {
+                if let Some(ecosystems) = Self::strings(value) {
+                    self.ecosystems = ecosystems;
+                }
+            }
+            "cache_ttl" => {
+                if let Some(cache_ttl) = value.as_u64() {
+                    self.cache_ttl = cache_ttl;
+                }
+            }
+            "display" => {
+                if let Some(display) = value.as_str() {
+                    self.display = display.to_string();
+                }
+            }
+            "timeout" => {
+                if let Some(timeout) = value.as_u64() {
+                    self.timeout = timeout;
+                }
+            }
+            "retries" => {
+                if let Some(retries) = value.as_u64() {
+                    self.retries = retries;
+                }
+            }
+            "abandoned_after" => {
+                if let Some(abandoned_after) = value.as_u64() {
+                    self.abandoned_after = abandoned_after;
+                }
+            }
+            "ca_bundle" => {
+                self.ca_bundle = value.as_str().map(|ca_bundle| ca_bundle.to_string());
+            }
+            "user_agent" => {
+                self.user_agent = value.as_str().map(|user_agent| user_agent.to_string());
+            }
+            "advisory_db" => {
+                self.advisory_db = value.as_str().map(|advisory_db| advisory_db.to_string());
+            }
+            "osv_db" => {
+                if let Some(osv_db) = Self::strings(value) {
+                    self.osv_db = osv_db;
+                }
+            }
+            "license_policy" => {
+                if let Some(policy) = value.as_map() {
+                    let mut license_policy = Policy::default();
+                    for (key, value) in policy {
+                        match (key.as_str(), Self::strings(value)) {
+                            (Some("allow"), Some(allow)) => license_policy.allow = allow,
+                            (Some("deny"), Some(deny)) => license_policy.deny = deny,
+                            _ => (),
+                        }
+                    }
+                    self.license_policy = license_policy;
+                }
+            }
+            "registries" => {
+                if let Some(registries) = value.as_map() {
+                    self.registries = registries
+                        .iter()
+                        .filter_map(|(ecosystem, registry)| {
+                            Some((ecosystem.as_str()?.to_string(), Self::registry(registry)?))
+                        })
+                        .collect();
+                }
+            }
+            _ => (),
+        }
+    }
+
+    // A registry is either its url, or a table with the url and a token
+    fn registry(value: &Value) -> Option<Registry> {
+        if let Some(url) = value.as_str() {
+            return Some(Registry::new(url));
+        }
+        let mut registry: Option<Registry> = None;
+        let mut token: Option<String> = None;
+        for (key, value) in value.as_map()? {
+            match key.as_str() {
+                Some("url") => registry = value.as_str().map(Registry::new),
+                Some("token") => token = value.as_str().map(|token| token.to_string()),
+                _ => (),
+            }
+        }
+        registry.map(|registry| Registry { token, ..registry })
+    }
+
+    fn strings(value: &Value) -> Option<Vec<String>> {
+        Some(
+            value
+                .as_array()?
+                .iter()
+                .filter_map(|item| item.as_str())
+                .map(|item| item.to_string())
+                .collect(),
+        )
+    }
+
+    pub fn show(&self, field: &str) -> bool {
+        self.fields.iter().any(|f| f == field)
+    }
+
+    // Whether licenses or release dates of each version are needed, some registries
+    // only give them in their full metadata
+    pub fn version_details(&self) -> bool {
+        self.show("license")
+            || self.show("age")
+            || self.show("behind_since")
+            || !self.license_policy.is_empty()
+    }
+
+    pub fn enabled(&self, ecosystem: &str) -> bool {
+        self.ecosystems.iter().any(|e| e == ecosystem)
+    }
+
+    pub fn virtual_text(&self) -> bool {
+        self.display != "diagnostics"
+    }
+
+    pub fn diagnostics(&self) -> bool {
+        self.display != "virtual_text"
+    }
+}
@@ -1,19 +1,38 @@
 use super::cache::Cache;
-use super::neovim_session::NeovimSession;
+use super::config::Config;
+use super::neovim_session::{Diagnostic, NeovimSession, QuickfixEntry, Severity};
 
+use crate::advisory::{self, Advisory};
+use crate::changelog;
 use crate::consts;
-use crate::parser::{CargoParser, PackageJsonParser, Parser, PipfileParser};
-use crate::store::{Cratesio, Npm, Pypi, Store};
+use crate::parser::{CargoParser, Drift, PackageJsonParser, Parser, PipfileParser};
+use crate::store::{
+    self, check_dependency, now, Behind, Check, Cratesio, Npm, Package, Pypi, Store, StoreError,
+    Update, UpdateKind,
+};
+use crate::upgrade::{Level, Upgrade};
 
-use failure::Error;
+use failure::{format_err, Error};
+use neovim_lib::Value;
 use rayon::prelude::*;
+use std::cmp::Ordering;
 use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::thread;
 
+#[derive(Clone)]
 pub struct DependencyInfo {
     pub name: String,
     pub requirement: String,
     pub current: String,
     pub latest: Vec<(String, String)>,
+    pub update: Option<Update>,
+    // License of the current version, from the registry
+    pub license: Option<String>,
+    // Unix timestamps of the release of the current version, and of the latest release
+    pub released: Option<u64>,
+    pub last_release: Option<u64>,
     pub line_number: i64,
 }
 
@@ -21,6 +40,12 @@ pub enum Messages {
     CargoToml,
     Pipfile,
     PackageJson,
+    UpgradeAll,
+    Outdated,
+    OutdatedAll,
+    Hover,
+    Changelog,
+    Configure,
     Unknown(String),
 }
 
@@ -30,11 +55,29 @@ impl From<String> for Messages {
             "cargo-toml" => Messages::CargoToml,
             "pipfile" => Messages::Pipfile,
             "package-json" => Messages::PackageJson,
+            "upgrade-all" => Messages::UpgradeAll,
+            "outdated" => Messages::Outdated,
+            "outdated-all" => Messages::OutdatedAll,
+            "hover" => Messages::Hover,
+            "changelog" => Messages::Changelog,
+            "configure" => Messages::Configure,
             _ => Messages::Unknown(event),
         }
     }
 }
 
+impl Messages {
+    // The ecosystem a manifest message belongs to, if any
+    fn ecosystem(&self) -> Option<&str> {
+        match self {
+            Messages::CargoToml => Some("cargo"),
+            Messages::Pipfile => Some("pypi"),
+            Messages::PackageJson => Some("npm"),
+            _ => None,
+        }
+    }
+}
+
 pub struct EventHandler;
 
 impl EventHandler {
@@ -42,116 +85,934 @@ impl EventHandler {
         content: &str,
         lockfile_content: &str,
         nvim_session: &mut NeovimSession,
-        mut cache: &mut Cache,
+        cache: &Cache,
+        config: &Config,
     ) -> Result<(), Error> {
         let dependencies: Vec<DependencyInfo> =
-            CargoParser::get_dependencies(&content, &lockfile_content)?;
-        Self::handle_store(&dependencies, &mut cache, nvim_session, Cratesio);
+            CargoParser::get_dependencies(content, lockfile_content)?;
+        let toolchain = CargoParser::get_toolchain(content);
+        let drift = CargoParser::get_drift(content, lockfile_content)?;
+        let store = Arc::new(Cratesio::from_config(config));
+        Self::handle_store(
+            &dependencies,
+            cache,
+            config,
+            nvim_session,
+            store,
+            toolchain,
+            drift.as_deref(),
+        );
         Ok(())
     }
 
     fn handle_pipfile(
         content: &str,
         lockfile_content: &str,
+        manifest_dir: &Path,
         nvim_session: &mut NeovimSession,
-        mut cache: &mut Cache,
+        cache: &Cache,
+        config: &Config,
     ) -> Result<(), Error> {
-        let dependencies: Vec<DependencyInfo> =
-            PipfileParser::get_dependencies(&content, &lockfile_content)?;
-        Self::handle_store(&dependencies, &mut cache, nvim_session, Pypi);
+        let mut dependencies: Vec<DependencyInfo> =
+            PipfileParser::get_dependencies(content, lockfile_content)?;
+        if lockfile_content.is_empty() {
+            PipfileParser::resolve_installed(&mut dependencies, manifest_dir);
+        }
+        let toolchain = PipfileParser::get_toolchain(content);
+        let drift = PipfileParser::get_drift(content, lockfile_content)?;
+        let store = Arc::new(Pypi::from_config(config));
+        Self::handle_store(
+            &dependencies,
+            cache,
+            config,
+            nvim_session,
+            store,
+            toolchain,
+            drift.as_deref(),
+        );
         Ok(())
     }
 
     fn handle_package_json(
         content: &str,
         lockfile_content: &str,
+        manifest_dir: &Path,
         nvim_session: &mut NeovimSession,
-        mut cache: &mut Cache,
+        cache: &Cache,
+        config: &Config,
     ) -> Result<(), Error> {
-        let dependencies: Vec<DependencyInfo> =
-            PackageJsonParser::get_dependencies(&content, &lockfile_content)?;
-        Self::handle_store(&dependencies, &mut cache, nvim_session, Npm);
+        let mut dependencies: Vec<DependencyInfo> =
+            PackageJsonParser::get_dependencies(content, lockfile_content)?;
+        if lockfile_content.is_empty() {
+            PackageJsonParser::resolve_installed(&mut dependencies, manifest_dir);
+        }
+        let toolchain = PackageJsonParser::get_toolchain(content);
+        let drift = PackageJsonParser::get_drift(content, lockfile_content)?;
+        let store = Arc::new(Npm::from_config(config));
+        Self::handle_store(
+            &dependencies,
+            cache,
+            config,
+            nvim_session,
+            store,
+            toolchain,
+            drift.as_deref(),
+        );
         Ok(())
     }
 
-    fn handle_store<T: Store>(
-        dependencies: &Vec<DependencyInfo>,
-        cache: &mut Cache,
+    // Check the dependency, along with the newest version the manifest's toolchain can use
+    fn check_compatible(
+        dep: &DependencyInfo,
+        package: Result<&Package, &StoreError>,
+        config: &Config,
+        store: &dyn Store,
+        toolchain: Option<&str>,
+    ) -> Check {
+        let (latest, mut update) = check_dependency(store, dep, package, config);
+        if let (Ok(package), Some(update), Some(toolchain)) = (package, &mut update, toolchain) {
+            // Only worth showing when it is an upgrade of its own
+            let compatible =
+                store::newest_compatible(store, package, toolchain).filter(|compatible| {
+                    store.compare_versions(compatible, &dep.current) == Some(Ordering::Greater)
+                });
+            if compatible.as_ref() != Some(&update.version) {
+                update.compatible = compatible;
+            }
+        }
+        (latest, update)
+    }
+
+    fn check_store(
+        dependencies: &[DependencyInfo],
+        cache: &Cache,
+        config: &Config,
+        store: Arc<dyn Store>,
+        toolchain: Option<&str>,
+    ) -> Vec<DependencyInfo> {
+        let registry = store.get_cache_key();
+        let pool = store.get_pool();
+        let (dependencies, refresh): (Vec<DependencyInfo>, Vec<bool>) = pool.install(|| {
+            dependencies
+                .par_iter()
+                .map(|dep| {
+                    let (package, refresh) = match cache.get(&registry, &dep.name) {
+                        Some((package, refresh)) => (Ok(package), refresh),
+                        None => {
+                            let package = store.get_package(&dep.name, config);
+                            if let Ok(package) = &package {
+                                cache.insert(&registry, &dep.name, package.clone());
+                            }
+                            (package, false)
+                        }
+                    };
+                    // Rendering depends on the dependency, only the registry metadata is cached
+                    let (latest, update) =
+                        Self::check_compatible(dep, package.as_ref(), config, &*store, toolchain);
+                    let package = package.as_ref().ok();
+                    // Only looked up when shown or checked, it can take another request
+                    let license = match package {
+                        Some(package) if config.version_details() => {
+                            store.get_license(package, &dep.name, &dep.current, config)
+                        }
+                        _ => None,
+                    };
+                    let released =
+                        package.and_then(|package| package.released.get(&dep.current).copied());
+                    let last_release = package.and_then(|package| package.last_release);
+                    let dep = DependencyInfo {
+                        requirement: dep.requirement.clone(),
+                        current: dep.current.clone(),
+                        line_number: dep.line_number,
+                        name: dep.name.clone(),
+                        latest,
+                        update,
+                        license,
+                        released,
+                        last_release,
+                    };
+                    (dep, refresh)
+                })
+                .unzip()
+        });
+
+        // Stale results are shown right away, and refreshed in the background for the next time
+        let stale: Vec<String> = dependencies
+            .iter()
+            .zip(refresh)
+            .filter(|(_, refresh)| *refresh)
+            .map(|(dep, _)| dep.name.clone())
+            .collect();
+        if !stale.is_empty() {
+            let cache = cache.clone();
+            let config = config.clone();
+            thread::spawn(move || {
+                pool.install(|| {
+                    stale
+                        .par_iter()
+                        .for_each(|name| match store.get_package(name, &config) {
+                            Ok(package) => cache.insert(&registry, name, package),
+                            Err(_) => cache.refresh_failed(&registry, name),
+                        })
+                });
+            });
+        }
+        dependencies
+    }
+
+    fn handle_store(
+        dependencies: &[DependencyInfo],
+        cache: &Cache,
+        config: &Config,
         nvim_session: &mut NeovimSession,
-        _: T,
+        store: Arc<dyn Store>,
+        toolchain: Option<String>,
+        drift: Option<&[Drift]>,
     ) {
-        let dependencies = dependencies
-            .par_iter()
-            .map(|dep| DependencyInfo {
-                requirement: dep.requirement.clone(),
-                current: dep.current.clone(),
-                line_number: dep.line_number,
-                name: dep.name.clone(),
-                latest: cache.get(&dep, &<T as Store>::check_dependency),
-            })
-            .collect();
-        cache.update(&dependencies);
+        let toolchain = toolchain.as_deref();
+        let dependencies = Self::check_store(dependencies, cache, config, store.clone(), toolchain);
+        let mut diagnostics: Vec<Diagnostic> = vec![];
+        let mut mismatches = 0;
         for dep in dependencies {
-            let mut lines: Vec<(String, String)> = vec![];
-            match semver::VersionReq::parse(&dep.requirement) {
-                Ok(requirement) => {
-                    let current = match semver::Version::parse(&dep.current) {
-                        Ok(current) => current,
-                        _ => continue,
-                    };
-                    if requirement.matches(&current) {
-                        lines.append(&mut vec![(
-                            dep.current.to_string(),
-                            consts::GREY_HG.to_string(),
-                        )])
-                    } else {
-                        lines.append(&mut vec![(
-                            dep.current.to_string(),
-                            consts::RED_HG.to_string(),
-                        )]);
-                    }
-                }
-                _ => {
-                    lines.append(&mut vec![(
-                        dep.current.to_string(),
-                        consts::GREY_HG.to_string(),
-                    )]);
+            let mut lines: Vec<(String, String)> =
+                vec![(config.prefix.to_string(), consts::UP_TO_DATE_HG.to_string())];
+            let unlocked = drift.into_iter().flatten().any(|drift| match drift {
+                Drift::Unlocked { line_number, .. } => *line_number == dep.line_number,
+                _ => false,
+            });
+            if unlocked {
+                // The lockfile has no entry, and we don't look for installed versions then
+                lines.push((
+                    consts::UNLOCKED_MARKER.to_string(),
+                    consts::DRIFT_HG.to_string(),
+                ));
+                diagnostics.push(Diagnostic {
+                    line_number: dep.line_number,
+                    severity: Severity::Warning,
+                    message: format!("{}: missing from the lockfile", dep.name),
+                });
+            } else if dep.current.is_empty() {
+                // Neither locked nor installed
+                lines.push((
+                    consts::NOT_INSTALLED.to_string(),
+                    consts::NOT_INSTALLED_HG.to_string(),
+                ));
+            } else if store.satisfies(&dep.requirement, &dep.current) == Some(false) {
+                mismatches += 1;
+                lines.push((dep.current.to_string(), consts::MISMATCH_HG.to_string()));
+                diagnostics.push(Diagnostic {
+                    line_number: dep.line_number,
+                    severity: Severity::Error,
+                    message: format!(
+                        "{}: locked version {} does not match requirement {}",
+                        dep.name, dep.current, dep.requirement
+                    ),
+                });
+            } else {
+                lines.push((dep.current.to_string(), consts::UP_TO_DATE_HG.to_string()));
+            }
+            if !config.show("current") {
+                lines.truncate(1);
+            }
+            if let Some(update) = &dep.update {
+                let (severity, kind) = match update.kind {
+                    UpdateKind::Major => (Severity::Warning, "major"),
+                    UpdateKind::Minor => (Severity::Hint, "minor"),
+                    UpdateKind::Patch => (Severity::Hint, "patch"),
+                };
+                let mut message = format!(
+                    "{}: {} update available, {} -> {}",
+                    dep.name, kind, dep.current, update.version
+                );
+                if let (Some(compatible), Some(toolchain)) = (&update.compatible, toolchain) {
+                    message.push_str(&format!(
+                        ", {} with {} {}",
+                        compatible,
+                        store.toolchain(),
+                        toolchain
+                    ));
                 }
+                if let Some(behind) = Self::behind(&update.behind, false) {
+                    message.push_str(&format!(" ({})", behind));
+                }
+                diagnostics.push(Diagnostic {
+                    line_number: dep.line_number,
+                    severity,
+                    message,
+                });
+            }
+            if config.show("latest") {
+                lines.append(&mut dep.latest.clone());
+                let compatible = dep
+                    .update
+                    .as_ref()
+                    .and_then(|update| update.compatible.as_ref());
+                if let (Some(compatible), Some(toolchain)) = (compatible, toolchain) {
+                    lines.push((
+                        format!(" ({} with {} {})", compatible, store.toolchain(), toolchain),
+                        consts::COMPATIBLE_HG.to_string(),
+                    ));
+                }
+            }
+            if let (true, Some(update)) = (config.show("behind"), &dep.update) {
+                if let Some(behind) = Self::behind(&update.behind, config.show("behind_since")) {
+                    lines.push((format!(" ({})", behind), consts::BEHIND_HG.to_string()));
+                }
+            }
+            let violation = config.license_policy.violation(dep.license.as_deref());
+            if config.show("license") || violation.is_some() {
+                let license = dep.license.as_deref().unwrap_or("unknown license");
+                let highlight = match violation {
+                    Some(_) => consts::LICENSE_VIOLATION_HG,
+                    None => consts::LICENSE_HG,
+                };
+                lines.push((format!(" {}", license), highlight.to_string()));
+            }
+            if let Some(violation) = violation {
+                diagnostics.push(Diagnostic {
+                    line_number: dep.line_number,
+                    severity: Severity::Error,
+                    message: format!("{}: {}", dep.name, violation),
+                });
+            }
+            if let (true, Some(released)) = (config.show("age"), dep.released) {
+                lines.push((
+                    format!(" {} old", Self::age(released)),
+                    consts::AGE_HG.to_string(),
+                ));
+            }
+            if let Some(last_release) = Self::abandoned(dep.last_release, config) {
+                let age = Self::age(last_release);
+                lines.push((
+                    format!(" no release in {}", age),
+                    consts::ABANDONED_HG.to_string(),
+                ));
+                diagnostics.push(Diagnostic {
+                    line_number: dep.line_number,
+                    severity: Severity::Warning,
+                    message: format!("{}: no release in {}, it may be abandoned", dep.name, age),
+                });
+            }
+            let advisories =
+                advisory::get_advisories(store.ecosystem(), &dep.name, &dep.current, config);
+            if let Some(marker) = Self::advisory_marker(&advisories) {
+                lines.push((marker, consts::ADVISORY_HG.to_string()));
+            }
+            for advisory in &advisories {
+                // Notices like unmaintained crates are not vulnerabilities
+                let severity = match (&advisory.informational, advisory.severity) {
+                    (Some(_), _) | (None, advisory::Severity::Low) => Severity::Warning,
+                    _ => Severity::Error,
+                };
+                diagnostics.push(Diagnostic {
+                    line_number: dep.line_number,
+                    severity,
+                    message: format!("{}: {} {}", dep.name, advisory.id, advisory.title),
+                });
+            }
+            if config.virtual_text() {
+                nvim_session.set_text(&lines, dep.line_number);
+            }
+        }
+        if config.diagnostics() {
+            nvim_session.set_diagnostics(&diagnostics);
+        }
+        if config.virtual_text() {
+            // Versions read from the installed packages are not the lockfile's business
+            let banner = match drift {
+                Some(drift) => Self::drift_banner(drift, mismatches, store.ecosystem()),
+                None => vec![],
             };
-            lines.append(&mut dep.latest.clone());
-            nvim_session.set_text(&lines, dep.line_number);
+            nvim_session.set_banner(&banner);
+        }
+    }
+
+    /* What the lockfile is missing or has too much, summed up at the top of the manifest.
+     * Empty when the lockfile is in sync, so a previous banner goes away
+     */
+    fn drift_banner(drift: &[Drift], mismatches: usize, ecosystem: &str) -> Vec<String> {
+        let unlocked = drift
+            .iter()
+            .filter(|drift| matches!(drift, Drift::Unlocked { .. }))
+            .count();
+        let unused: Vec<&str> = drift
+            .iter()
+            .filter_map(|drift| match drift {
+                Drift::Unused(name) => Some(name.as_str()),
+                _ => None,
+            })
+            .collect();
+        let plural = |count: usize| if count == 1 { "y" } else { "ies" };
+        let mut problems: Vec<String> = vec![];
+        if drift.contains(&Drift::HashMismatch) {
+            problems.push("the manifest changed since it was locked".to_string());
+        }
+        if unlocked > 0 {
+            problems.push(format!(
+                "{} dependenc{} missing from it",
+                unlocked,
+                plural(unlocked)
+            ));
+        }
+        if mismatches > 0 {
+            problems.push(format!(
+                "{} locked version{} not matching the requirement",
+                mismatches,
+                if mismatches == 1 { "" } else { "s" }
+            ));
+        }
+        if !unused.is_empty() {
+            problems.push(format!(
+                "{} dependenc{} no longer in the manifest: {}",
+                unused.len(),
+                plural(unused.len()),
+                unused.join(", ")
+            ));
+        }
+        if problems.is_empty() {
+            return vec![];
+        }
+        let relock = match ecosystem {
+            "cargo" => "cargo update --workspace",
+            "npm" => "yarn install",
+            _ => "pipenv lock",
+        };
+        let mut banner = vec![format!(
+            "{}Lockfile out of date, run `{}`",
+            consts::DRIFT_MARKER,
+            relock
+        )];
+        banner.extend(
+            problems
+                .into_iter()
+                .map(|problem| format!("  - {}", problem)),
+        );
+        banner
+    }
+
+    // Time elapsed since the timestamp, in the largest unit that makes sense
+    fn age(timestamp: u64) -> String {
+        let days = now().saturating_sub(timestamp) / (24 * 60 * 60);
+        match days {
+            0 => "less than a day".to_string(),
+            1..=59 => format!("{}d", days),
+            60..=729 => format!("{}mo", days / 30),
+            _ => format!("{}y", days / 365),
+        }
+    }
+
+    // How many releases the dependency is behind, and since when
+    fn behind(behind: &Behind, since: bool) -> Option<String> {
+        if behind.total() == 0 {
+            return None;
+        }
+        let kinds: Vec<String> = [
+            (behind.major, "major"),
+            (behind.minor, "minor"),
+            (behind.patch, "patch"),
+        ]
+        .iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, kind)| format!("{} {}", count, kind))
+        .collect();
+        let releases = if behind.total() == 1 {
+            "release"
+        } else {
+            "releases"
+        };
+        let mut text = format!("{} {} behind", kinds.join(", "), releases);
+        if let (true, Some(since)) = (since, behind.since) {
+            text.push_str(&format!(" since {}", store::date(since)));
+        }
+        Some(text)
+    }
+
+    // The last release, if it's older than the abandoned threshold
+    fn abandoned(last_release: Option<u64>, config: &Config) -> Option<u64> {
+        let last_release = last_release?;
+        let threshold = config.abandoned_after * 24 * 60 * 60;
+        if config.abandoned_after > 0 && now().saturating_sub(last_release) > threshold {
+            Some(last_release)
+        } else {
+            None
         }
     }
 
+    /* The most severe advisories first, along with the highest severity when known.
+     * Packages with a long history can have many, only the first ones are listed
+     */
+    fn advisory_marker(advisories: &[Advisory]) -> Option<String> {
+        let highest = advisories.first()?.severity;
+        let mut ids: Vec<String> = advisories
+            .iter()
+            .take(consts::MAX_ADVISORY_IDS)
+            .map(|advisory| advisory.id.clone())
+            .collect();
+        if advisories.len() > ids.len() {
+            ids.push(format!("+{}", advisories.len() - ids.len()));
+        }
+        Some(match highest {
+            advisory::Severity::Unknown => format!("{}{}", consts::ADVISORY_MARKER, ids.join(" ")),
+            severity => format!("{}{} {}", consts::ADVISORY_MARKER, severity, ids.join(" ")),
+        })
+    }
+
+    // Parse a manifest of any supported type, along with its ecosystem and toolchain
+    fn parse_manifest(
+        file_path: &str,
+        content: &str,
+    ) -> Result<(&'static str, Vec<DependencyInfo>, Option<String>), Error> {
+        if file_path.ends_with("Cargo.toml") {
+            let lockfile_content = Self::read_lockfile(&file_path.replace(".toml", ".lock"));
+            let dependencies = CargoParser::get_dependencies(content, &lockfile_content)?;
+            Ok(("cargo", dependencies, CargoParser::get_toolchain(content)))
+        } else if file_path.ends_with("Pipfile") {
+            let lockfile_content = Self::read_lockfile(&format!("{}.lock", file_path));
+            let mut dependencies = PipfileParser::get_dependencies(content, &lockfile_content)?;
+            if lockfile_content.is_empty() {
+                PipfileParser::resolve_installed(&mut dependencies, Self::manifest_dir(file_path));
+            }
+            Ok(("pypi", dependencies, PipfileParser::get_toolchain(content)))
+        } else if file_path.ends_with("package.json") {
+            let lockfile_content =
+                Self::read_lockfile(&file_path.replace("package.json", "yarn.lock"));
+            let mut dependencies = PackageJsonParser::get_dependencies(content, &lockfile_content)?;
+            if lockfile_content.is_empty() {
+                PackageJsonParser::resolve_installed(
+                    &mut dependencies,
+                    Self::manifest_dir(file_path),
+                );
+            }
+            Ok((
+                "npm",
+                dependencies,
+                PackageJsonParser::get_toolchain(content),
+            ))
+        } else {
+            Err(format_err!("Not a supported manifest: {}", file_path))
+        }
+    }
+
+    // Parse a manifest from disk and check its dependencies, whatever the type
+    fn check_manifest(
+        file_path: &str,
+        cache: &Cache,
+        config: &Config,
+    ) -> Result<Vec<DependencyInfo>, Error> {
+        let content = fs::read_to_string(file_path)?;
+        let (ecosystem, dependencies, toolchain) = Self::parse_manifest(file_path, &content)?;
+        let store = store::get_store(ecosystem, config)
+            .ok_or_else(|| format_err!("Unknown ecosystem: {}", ecosystem))?;
+        Ok(Self::check_store(
+            &dependencies,
+            cache,
+            config,
+            store,
+            toolchain.as_deref(),
+        ))
+    }
+
+    fn outdated_entries(file_path: &str, dependencies: &[DependencyInfo]) -> Vec<QuickfixEntry> {
+        let mut entries: Vec<QuickfixEntry> = dependencies
+            .iter()
+            .filter_map(|dep| {
+                let update = dep.update.as_ref()?;
+                Some(QuickfixEntry {
+                    file_path: file_path.to_string(),
+                    line_number: dep.line_number,
+                    text: format!("{} {} -> {}", dep.name, dep.current, update.version),
+                    warning: update.kind == UpdateKind::Major,
+                })
+            })
+            .collect();
+        entries.sort_by_key(|entry| entry.line_number);
+        entries
+    }
+
+    // Fill the location list with the outdated dependencies of a single manifest
+    fn handle_outdated(
+        file_path: &str,
+        nvim_session: &mut NeovimSession,
+        cache: &Cache,
+        config: &Config,
+    ) -> Result<(), Error> {
+        let dependencies = Self::check_manifest(file_path, cache, config)?;
+        let entries = Self::outdated_entries(file_path, &dependencies);
+        nvim_session.set_location_list(&entries);
+        Ok(())
+    }
+
+    // Fill the quickfix list with the outdated dependencies of every manifest given
+    fn handle_outdated_all(
+        file_paths: &[Value],
+        nvim_session: &mut NeovimSession,
+        cache: &Cache,
+        config: &Config,
+    ) -> Result<(), Error> {
+        let mut entries: Vec<QuickfixEntry> = vec![];
+        // A manifest that can't be read or parsed doesn't hide the others
+        let mut skipped: Vec<String> = vec![];
+        for file_path in file_paths.iter().filter_map(|file_path| file_path.as_str()) {
+            match Self::check_manifest(file_path, cache, config) {
+                Ok(dependencies) => {
+                    entries.append(&mut Self::outdated_entries(file_path, &dependencies))
+                }
+                Err(error) => skipped.push(format!("{} ({})", file_path, error)),
+            }
+        }
+        nvim_session.set_quickfix_list(&entries);
+        if !skipped.is_empty() {
+            nvim_session.echo(&format!("Skipped {}", skipped.join(", ")));
+        }
+        Ok(())
+    }
+
+    fn handle_upgrade_all(
+        file_path: &str,
+        content: &str,
+        level: &str,
+        config: &Config,
+        nvim_session: &mut NeovimSession,
+    ) -> Result<(), Error> {
+        let level: Level = level.parse()?;
+        let (ecosystem, dependencies, _) = Self::parse_manifest(file_path, content)?;
+        let store = store::get_store(ecosystem, config)
+            .ok_or_else(|| format_err!("Unknown ecosystem: {}", ecosystem))?;
+        Self::upgrade_dependencies(&dependencies, level, config, nvim_session, &*store);
+        Ok(())
+    }
+
+    fn upgrade_dependencies(
+        dependencies: &[DependencyInfo],
+        level: Level,
+        config: &Config,
+        nvim_session: &mut NeovimSession,
+        store: &dyn Store,
+    ) {
+        let mut upgrades: Vec<Upgrade> = store.get_pool().install(|| {
+            dependencies
+                .par_iter()
+                .filter_map(|dep| {
+                    let versions = store.get_versions(&dep.name, config).ok()?;
+                    Upgrade::new(dep, &versions, level)
+                })
+                .collect()
+        });
+        upgrades.sort_by_key(|upgrade| upgrade.line_number);
+
+        // Apply everything to a copy of the buffer first, so we only preview
+        // the upgrades we are actually able to write
+        let mut lines = nvim_session.get_lines();
+        let mut applied: Vec<&Upgrade> = vec![];
+        for upgrade in &upgrades {
+            let index = upgrade.line_number as usize;
+            if let Some(line) = lines.get(index).and_then(|line| upgrade.apply(line)) {
+                lines[index] = line;
+                applied.push(upgrade);
+            }
+        }
+        if applied.is_empty() {
+            nvim_session.echo("All dependencies are up to date");
+            return;
+        }
+
+        let width = applied.iter().map(|upgrade| upgrade.name.len()).max();
+        let preview: Vec<String> = applied
+            .iter()
+            .map(|upgrade| {
+                format!(
+                    "{:width$}  {} -> {}",
+                    upgrade.name,
+                    upgrade.old,
+                    upgrade.new,
+                    width = width.unwrap_or(0)
+                )
+            })
+            .collect();
+        let message = format!(
+            "Upgrade {} dependencies?\n{}",
+            applied.len(),
+            preview.join("\n")
+        );
+        if nvim_session.confirm(&message) {
+            // Replace the whole changed range at once, so a single undo reverts it
+            let first = applied[0].line_number as usize;
+            let last = applied[applied.len() - 1].line_number as usize;
+            nvim_session.set_lines(first as i64, last as i64 + 1, lines[first..=last].to_vec());
+        }
+    }
+
+    // Registry metadata for a single package, from the cache when possible
+    fn get_package(
+        store: &dyn Store,
+        name: &str,
+        cache: &Cache,
+        config: &Config,
+    ) -> Result<Package, StoreError> {
+        let registry = &store.get_cache_key();
+        if let Some((package, _)) = cache.get(registry, name) {
+            return Ok(package);
+        }
+        let package = store.get_package(name, config)?;
+        cache.insert(registry, name, package.clone());
+        Ok(package)
+    }
+
+    // Markdown describing a dependency, its latest version and what affects it
+    fn hover_lines(
+        dep: &DependencyInfo,
+        package: Result<&Package, &StoreError>,
+        advisories: &[Advisory],
+        config: &Config,
+        store: &dyn Store,
+        toolchain: Option<&str>,
+    ) -> Vec<String> {
+        let mut lines = vec![
+            format!("# {}", dep.name),
+            "".to_string(),
+            format!("- requirement: `{}`", dep.requirement),
+        ];
+        if dep.current.is_empty() {
+            lines.push(format!("- {}", consts::NOT_INSTALLED));
+        } else {
+            lines.push(format!("- locked: `{}`", dep.current));
+        }
+        match (
+            package,
+            Self::check_compatible(dep, package, config, store, toolchain),
+        ) {
+            (Err(error), _) => lines.push(format!("- latest: {}", error)),
+            (Ok(_), (_, Some(update))) => {
+                let kind = match update.kind {
+                    UpdateKind::Major => "major",
+                    UpdateKind::Minor => "minor",
+                    UpdateKind::Patch => "patch",
+                };
+                lines.push(format!("- latest: `{}`, {} update", update.version, kind));
+                if let Some(behind) = Self::behind(&update.behind, true) {
+                    lines.push(format!("- {}", behind));
+                }
+                if let (Some(compatible), Some(toolchain)) = (&update.compatible, toolchain) {
+                    lines.push(format!(
+                        "- newest with {} {}: `{}`",
+                        store.toolchain(),
+                        toolchain,
+                        compatible
+                    ));
+                }
+            }
+            (Ok(package), (_, None)) => {
+                lines.push(format!("- latest: `{}`", package.max_version));
+            }
+        }
+        if let Ok(package) = package {
+            if let Some(released) = package.released.get(&dep.current) {
+                lines.push(format!(
+                    "- locked version released {} ago",
+                    Self::age(*released)
+                ));
+            }
+            if let Some(last_release) = package.last_release {
+                let abandoned = match Self::abandoned(Some(last_release), config) {
+                    Some(_) => ", it may be abandoned",
+                    None => "",
+                };
+                let age = Self::age(last_release);
+                lines.push(format!("- last release {} ago{}", age, abandoned));
+            }
+            let license = store.get_license(package, &dep.name, &dep.current, config);
+            let license = license.as_deref();
+            match (license, config.license_policy.violation(license)) {
+                (_, Some(violation)) => lines.push(format!("- license: {}", violation)),
+                (Some(license), None) => lines.push(format!("- license: `{}`", license)),
+                (None, None) => (),
+            }
+            if let Some(repository) = &package.repository {
+                lines.push(format!("- repository: {}", repository));
+            }
+        }
+        for advisory in advisories {
+            lines.push("".to_string());
+            lines.push(format!("## {}", advisory.id));
+            lines.push(advisory.title.to_string());
+            lines.push("".to_string());
+            if let Some(informational) = &advisory.informational {
+                lines.push(format!("- informational: {}", informational));
+            }
+            if advisory.severity != advisory::Severity::Unknown {
+                lines.push(format!("- severity: {}", advisory.severity));
+            }
+            if !advisory.aliases.is_empty() {
+                lines.push(format!("- aliases: {}", advisory.aliases.join(", ")));
+            }
+            lines.push(format!("- date: {}", advisory.date));
+            if advisory.patched.is_empty() {
+                lines.push("- patched: no fixed version".to_string());
+            } else {
+                lines.push(format!("- patched: `{}`", advisory.patched.join("`, `")));
+            }
+            if let Some(url) = &advisory.url {
+                lines.push(format!("- {}", url));
+            }
+        }
+        lines
+    }
+
+    // Show what we know about the dependency declared on the given line
+    fn handle_hover(
+        file_path: &str,
+        content: &str,
+        line_number: i64,
+        cache: &Cache,
+        config: &Config,
+        nvim_session: &mut NeovimSession,
+    ) -> Result<(), Error> {
+        let (ecosystem, dependencies, toolchain) = Self::parse_manifest(file_path, content)?;
+        let dep = dependencies
+            .iter()
+            .find(|dep| dep.line_number == line_number)
+            .ok_or_else(|| format_err!("No dependency on this line"))?;
+        let store = store::get_store(ecosystem, config)
+            .ok_or_else(|| format_err!("Unknown ecosystem: {}", ecosystem))?;
+        let package = Self::get_package(&*store, &dep.name, cache, config);
+        let advisories = advisory::get_advisories(ecosystem, &dep.name, &dep.current, config);
+        let lines = Self::hover_lines(
+            dep,
+            package.as_ref(),
+            &advisories,
+            config,
+            &*store,
+            toolchain.as_deref(),
+        );
+        nvim_session.show_hover(lines);
+        Ok(())
+    }
+
+    // Markdown with the release notes between the locked and the latest version
+    fn changelog_lines(
+        dep: &DependencyInfo,
+        package: &Package,
+        found: Option<(String, String)>,
+    ) -> Vec<String> {
+        let title = match &dep.current[..] {
+            "" => format!("# {} {}", dep.name, package.max_version),
+            current => format!("# {} {} -> {}", dep.name, current, package.max_version),
+        };
+        let mut lines = vec![title, "".to_string()];
+        if let Some(repository) = &package.repository {
+            lines.push(format!("- repository: {}", repository));
+        }
+        let (source, content) = match found {
+            Some(found) => found,
+            None => {
+                lines.push("- no changelog found".to_string());
+                return lines;
+            }
+        };
+        lines.push(format!("- changelog: {}", source));
+        lines.push("".to_string());
+        match changelog::sections(&content, &dep.current, &package.max_version) {
+            Some(sections) if sections.is_empty() => {
+                lines.push("No release notes for these versions".to_string());
+            }
+            Some(mut sections) => lines.append(&mut sections),
+            // Without version headings, all of it is relevant
+            None => lines.extend(content.lines().map(|line| line.to_string())),
+        }
+        lines
+    }
+
+    // Show the release notes of the dependency declared on the given line
+    fn handle_changelog(
+        file_path: &str,
+        content: &str,
+        line_number: i64,
+        cache: &Cache,
+        config: &Config,
+        nvim_session: &mut NeovimSession,
+    ) -> Result<(), Error> {
+        let (ecosystem, dependencies, _) = Self::parse_manifest(file_path, content)?;
+        let dep = dependencies
+            .iter()
+            .find(|dep| dep.line_number == line_number)
+            .ok_or_else(|| format_err!("No dependency on this line"))?;
+        let store = store::get_store(ecosystem, config)
+            .ok_or_else(|| format_err!("Unknown ecosystem: {}", ecosystem))?;
+        let package = Self::get_package(&*store, &dep.name, cache, config)?;
+        let found = changelog::find(
+            ecosystem,
+            &dep.name,
+            &package.max_version,
+            &dep.current,
+            Self::manifest_dir(file_path),
+            || {
+                store
+                    .get_tarball(&dep.name, &package.max_version, config)
+                    .ok()?
+            },
+        );
+        nvim_session.show_scratch(Self::changelog_lines(dep, &package, found));
+        Ok(())
+    }
+
+    fn handle_configure(args: &[Value], config: &mut Config, cache: &mut Cache) {
+        if let Some(options) = args.first() {
+            config.configure(options);
+        }
+        cache.set_duration(config.cache_ttl);
+    }
+
+    // Directory of the manifest, where packages get installed
+    fn manifest_dir(file_path: &str) -> &Path {
+        Path::new(file_path).parent().unwrap_or(Path::new("."))
+    }
+
+    fn read_lockfile(lockfile_path: &str) -> String {
+        // Parse lock file, or use an empty string
+        fs::read_to_string(lockfile_path).unwrap_or_else(|_| "".to_string())
+    }
+
+    // The manifest a message is about and its content, its buffer becomes the current one
+    fn read_buffer<'a>(
+        args: &'a [Value],
+        nvim_session: &mut NeovimSession,
+    ) -> Option<(&'a str, String)> {
+        nvim_session.buffer_number = args.get(1)?.as_i64()?;
+        let file_path = args.first()?.as_str()?;
+        let manifest_content = fs::read_to_string(file_path).ok()?;
+        Some((file_path, manifest_content))
+    }
+
     pub fn recv(nvim_session: &mut NeovimSession) {
         let receiver = nvim_session.start_event_loop_channel();
-        let mut cargo_cache: Cache = Cache::new(30);
-        let mut pypi_cache: Cache = Cache::new(30);
-        let mut npm_cache: Cache = Cache::new(30);
+        let mut config = Config::load(nvim_session);
+        let mut cache = Cache::new(config.cache_ttl);
 
         for (event, args) in receiver {
-            nvim_session.buffer_number = match args[1].as_i64() {
-                Some(number) => number,
-                _ => continue,
-            };
-            let file_path = match args[0].as_str() {
-                Some(file_path) => file_path,
-                _ => continue,
-            };
-            let manifest_content = match fs::read_to_string(&file_path) {
-                Ok(content) => content,
-                _ => continue,
-            };
-            match Messages::from(event) {
-                Messages::CargoToml => {
-                    // Parse lock file, or use an empty string
-                    let lockfile_content = fs::read_to_string(file_path.replace(".toml", ".lock"))
-                        .unwrap_or("".to_string());
+            let message = Messages::from(event);
+            if let Some(ecosystem) = message.ecosystem() {
+                if !config.enabled(ecosystem) {
+                    continue;
+                }
+            }
+            match (message, Self::read_buffer(&args, nvim_session)) {
+                // Configuration is the only message not related to a buffer
+                (Messages::Configure, _) => {
+                    Self::handle_configure(&args, &mut config, &mut cache);
+                }
+                (Messages::Unknown(event), _) => {
+                    nvim_session.echo(&format!("Unkown command: {}, args: {:?}", event, args));
+                }
+                (_, None) => (),
+                (Messages::CargoToml, Some((file_path, manifest_content))) => {
+                    let lockfile_content =
+                        Self::read_lockfile(&file_path.replace(".toml", ".lock"));
                     match Self::handle_cargo_toml(
                         &manifest_content,
                         &lockfile_content,
                         nvim_session,
-                        &mut cargo_cache,
+                        &cache,
+                        &config,
                     ) {
                         Ok(_) => (),
                         Err(error) => {
@@ -159,15 +1020,15 @@ impl EventHandler {
                         }
                     };
                 }
-                Messages::Pipfile => {
-                    // Parse lock file, or use an empty string
-                    let lockfile_content =
-                        fs::read_to_string(format!("{}.lock", file_path)).unwrap_or("".to_string());
+                (Messages::Pipfile, Some((file_path, manifest_content))) => {
+                    let lockfile_content = Self::read_lockfile(&format!("{}.lock", file_path));
                     match Self::handle_pipfile(
                         &manifest_content,
                         &lockfile_content,
+                        Self::manifest_dir(file_path),
                         nvim_session,
-                        &mut pypi_cache,
+                        &cache,
+                        &config,
                     ) {
                         Ok(_) => (),
                         Err(error) => {
@@ -175,16 +1036,16 @@ impl EventHandler {
                         }
                     };
                 }
-                Messages::PackageJson => {
-                    // Parse lock file, or use an empty string
+                (Messages::PackageJson, Some((file_path, manifest_content))) => {
                     let lockfile_content =
-                        fs::read_to_string(file_path.replace("package.json", "yarn.lock"))
-                            .unwrap_or("".to_string());
+                        Self::read_lockfile(&file_path.replace("package.json", "yarn.lock"));
                     match Self::handle_package_json(
                         &manifest_content,
                         &lockfile_content,
+                        Self::manifest_dir(file_path),
                         nvim_session,
-                        &mut npm_cache,
+                        &cache,
+                        &config,
                     ) {
                         Ok(_) => (),
                         Err(error) => {
@@ -192,8 +1053,84 @@ impl EventHandler {
                         }
                     };
                 }
-                Messages::Unknown(event) => {
-                    nvim_session.echo(&format!("Unkown command: {}, args: {:?}", event, args));
+                (Messages::UpgradeAll, Some((file_path, manifest_content))) => {
+                    // Upgrade level is optional, default to minor upgrades, which stay semver compatible
+                    let level = args
+                        .get(2)
+                        .and_then(|level| level.as_str())
+                        .unwrap_or("minor");
+                    match Self::handle_upgrade_all(
+                        file_path,
+                        &manifest_content,
+                        level,
+                        &config,
+                        nvim_session,
+                    ) {
+                        Ok(_) => (),
+                        Err(error) => {
+                            nvim_session.echo(&error.to_string());
+                        }
+                    };
+                }
+                (Messages::Outdated, Some((file_path, _))) => {
+                    match Self::handle_outdated(file_path, nvim_session, &cache, &config) {
+                        Ok(_) => (),
+                        Err(error) => {
+                            nvim_session.echo(&error.to_string());
+                        }
+                    };
+                }
+                (Messages::OutdatedAll, Some((file_path, _))) => {
+                    // Manifests open in the editor, the current one is always included
+                    let file_paths = match args.get(2).and_then(|paths| paths.as_array()) {
+                        Some(file_paths) => file_paths.to_vec(),
+                        None => vec![Value::from(file_path)],
+                    };
+                    match Self::handle_outdated_all(&file_paths, nvim_session, &cache, &config) {
+                        Ok(_) => (),
+                        Err(error) => {
+                            nvim_session.echo(&error.to_string());
+                        }
+                    };
+                }
+                (Messages::Hover, Some((file_path, manifest_content))) => {
+                    // Cursor line, 0-based like the dependency line numbers
+                    let line_number = match args.get(2).and_then(|line| line.as_i64()) {
+                        Some(line_number) => line_number,
+                        None => continue,
+                    };
+                    match Self::handle_hover(
+                        file_path,
+                        &manifest_content,
+                        line_number,
+                        &cache,
+                        &config,
+                        nvim_session,
+                    ) {
+                        Ok(_) => (),
+                        Err(error) => {
+                            nvim_session.echo(&error.to_string());
+                        }
+                    };
+                }
+                (Messages::Changelog, Some((file_path, manifest_content))) => {
+                    let line_number = match args.get(2).and_then(|line| line.as_i64()) {
+                        Some(line_number) => line_number,
+                        None => continue,
+                    };
+                    match Self::handle_changelog(
+                        file_path,
+                        &manifest_content,
+                        line_number,
+                        &cache,
+                        &config,
+                        nvim_session,
+                    ) {
+                        Ok(_) => (),
+                        Err(error) => {
+                            nvim_session.echo(&error.to_string());
+                        }
+                    };
                 }
             }
         }
@@ -1,10 +1,12 @@
 mod cache;
+mod config;
 mod event_handler;
 mod neovim_session;
 
 use event_handler::EventHandler;
 use neovim_session::NeovimSession;
 
+pub use config::Config;
 pub use event_handler::DependencyInfo;
 
 pub fn run() {
@@ -2,6 +2,27 @@ use crate::consts;
 use neovim_lib::neovim_api::Buffer;
 use neovim_lib::{Neovim, NeovimApi, Session, Value};
 
+// Values match vim.diagnostic.severity
+#[derive(Clone, Copy)]
+pub enum Severity {
+    Error = 1,
+    Warning = 2,
+    Hint = 4,
+}
+
+pub struct Diagnostic {
+    pub line_number: i64,
+    pub severity: Severity,
+    pub message: String,
+}
+
+pub struct QuickfixEntry {
+    pub file_path: String,
+    pub line_number: i64,
+    pub text: String,
+    pub warning: bool,
+}
+
 pub struct NeovimSession {
     pub nvim: Neovim,
     pub buffer_number: i64,
@@ -17,27 +38,27 @@ impl NeovimSession {
         }
     }
 
+    pub fn get_global(&mut self, name: &str) -> Option<Value> {
+        self.nvim.get_var(name).ok()
+    }
+
     pub fn echo(&mut self, message: &str) {
         self.nvim.command(&format!("echo \"{}\"", message)).unwrap();
     }
 
     fn get_buffer(&mut self) -> Option<Buffer> {
         let buffers = self.nvim.list_bufs().expect("Error listing buffers");
-        for buf in buffers {
-            if buf
-                .get_number(&mut self.nvim)
+        let buffer_number = self.buffer_number;
+        buffers.into_iter().find(|buf| {
+            buf.get_number(&mut self.nvim)
                 .expect("Error getting buffer number")
-                == self.buffer_number
-            {
-                return Some(buf);
-            }
-        }
-        None
+                == buffer_number
+        })
     }
 
-    pub fn set_text(&mut self, messages: &Vec<(String, String)>, line_number: i64) {
+    pub fn set_text(&mut self, messages: &[(String, String)], line_number: i64) {
         if let Some(buffer) = self.get_buffer() {
-            let mut chunks: Vec<Value> = messages
+            let chunks: Vec<Value> = messages
                 .iter()
                 .map(|(message, highlight)| {
                     vec![
@@ -47,10 +68,6 @@ impl NeovimSession {
                     .into()
                 })
                 .collect();
-            chunks.insert(
-                0,
-                vec![Value::from(consts::PREFIX), Value::from(consts::GREY_HG)].into(),
-            );
             match buffer.set_virtual_text(&mut self.nvim, 0, line_number, chunks, vec![]) {
                 Ok(_) => (),
                 Err(error) => self.echo(&format!("{}", error)),
@@ -58,6 +75,152 @@ impl NeovimSession {
         }
     }
 
+    // Publish diagnostics through vim.diagnostic, replacing the ones previously set on the buffer
+    pub fn set_diagnostics(&mut self, diagnostics: &[Diagnostic]) {
+        let diagnostics: Vec<Value> = diagnostics
+            .iter()
+            .map(|diagnostic| {
+                Value::Map(vec![
+                    (Value::from("lnum"), Value::from(diagnostic.line_number)),
+                    (Value::from("col"), Value::from(0)),
+                    (
+                        Value::from("severity"),
+                        Value::from(diagnostic.severity as i64),
+                    ),
+                    (
+                        Value::from("message"),
+                        Value::from(diagnostic.message.to_string()),
+                    ),
+                    (Value::from("source"), Value::from("package-info")),
+                ])
+            })
+            .collect();
+        let args = vec![Value::from(self.buffer_number), Value::from(diagnostics)];
+        if let Err(error) = self.nvim.execute_lua(consts::SET_DIAGNOSTICS_LUA, args) {
+            self.echo(&format!("{}", error));
+        }
+    }
+
+    // Show lines under the first one of the buffer, replacing the previous ones, or clear them
+    pub fn set_banner(&mut self, lines: &[String]) {
+        let lines: Vec<Value> = lines
+            .iter()
+            .map(|line| Value::from(line.to_string()))
+            .collect();
+        let args = vec![
+            Value::from(self.buffer_number),
+            Value::from(lines),
+            Value::from(consts::DRIFT_HG),
+        ];
+        if let Err(error) = self.nvim.execute_lua(consts::BANNER_LUA, args) {
+            self.echo(&format!("{}", error));
+        }
+    }
+
+    pub fn set_location_list(&mut self, entries: &[QuickfixEntry]) {
+        // Location list of the current window
+        self.set_list("setloclist", vec![Value::from(0)], entries, "lwindow");
+    }
+
+    pub fn set_quickfix_list(&mut self, entries: &[QuickfixEntry]) {
+        self.set_list("setqflist", vec![], entries, "cwindow");
+    }
+
+    fn set_list(
+        &mut self,
+        function: &str,
+        mut args: Vec<Value>,
+        entries: &[QuickfixEntry],
+        window: &str,
+    ) {
+        // Push a new list with a title, instead of replacing the items of the current one
+        args.push(Value::Array(vec![]));
+        args.push(Value::from(" "));
+        args.push(Self::quickfix_what(entries));
+        match self.nvim.call_function(function, args) {
+            Ok(_) => self.nvim.command(window).unwrap_or(()),
+            Err(error) => self.echo(&format!("{}", error)),
+        }
+    }
+
+    fn quickfix_what(entries: &[QuickfixEntry]) -> Value {
+        let items: Vec<Value> = entries
+            .iter()
+            .map(|entry| {
+                Value::Map(vec![
+                    (
+                        Value::from("filename"),
+                        Value::from(entry.file_path.to_string()),
+                    ),
+                    // Quickfix lines are 1-based
+                    (Value::from("lnum"), Value::from(entry.line_number + 1)),
+                    (Value::from("col"), Value::from(1)),
+                    (Value::from("text"), Value::from(entry.text.to_string())),
+                    (
+                        Value::from("type"),
+                        Value::from(if entry.warning { "W" } else { "I" }),
+                    ),
+                ])
+            })
+            .collect();
+        Value::Map(vec![
+            (Value::from("title"), Value::from("Outdated dependencies")),
+            (Value::from("items"), Value::from(items)),
+        ])
+    }
+
+    pub fn get_lines(&mut self) -> Vec<String> {
+        match self.get_buffer() {
+            Some(buffer) => buffer
+                .get_lines(&mut self.nvim, 0, -1, false)
+                .unwrap_or_default(),
+            None => vec![],
+        }
+    }
+
+    // Replace lines [start, end) with a single call, so it can be undone in one go
+    pub fn set_lines(&mut self, start: i64, end: i64, lines: Vec<String>) {
+        if let Some(buffer) = self.get_buffer() {
+            match buffer.set_lines(&mut self.nvim, start, end, false, lines) {
+                Ok(_) => (),
+                Err(error) => self.echo(&format!("{}", error)),
+            }
+        }
+    }
+
+    pub fn show_hover(&mut self, lines: Vec<String>) {
+        let lines: Vec<Value> = lines.into_iter().map(Value::from).collect();
+        if let Err(error) = self
+            .nvim
+            .execute_lua(consts::HOVER_LUA, vec![Value::from(lines)])
+        {
+            self.echo(&format!("{}", error));
+        }
+    }
+
+    // Open the lines in a new scratch buffer, in a split
+    pub fn show_scratch(&mut self, lines: Vec<String>) {
+        let lines: Vec<Value> = lines.into_iter().map(Value::from).collect();
+        if let Err(error) = self
+            .nvim
+            .execute_lua(consts::SCRATCH_LUA, vec![Value::from(lines)])
+        {
+            self.echo(&format!("{}", error));
+        }
+    }
+
+    pub fn confirm(&mut self, message: &str) -> bool {
+        let args = vec![
+            Value::from(message),
+            Value::from("&Yes\n&No"),
+            Value::from(2),
+        ];
+        match self.nvim.call_function("confirm", args) {
+            Ok(choice) => choice.as_i64() == Some(1),
+            Err(_) => false,
+        }
+    }
+
     pub fn start_event_loop_channel(&mut self) -> std::sync::mpsc::Receiver<(String, Vec<Value>)> {
         self.nvim.session.start_event_loop_channel()
     }
@@ -1,18 +1,27 @@
 use crate::consts;
 use crate::neovim::DependencyInfo;
-use crate::parser::{Lockfile, Manifest, Parser};
+use crate::parser::{toml_line_number, Lockfile, Manifest, Parser};
 use failure::Error;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 impl From<cargo_toml::Manifest> for Manifest {
     fn from(cargo_toml_manifest: cargo_toml::Manifest) -> Manifest {
-        let dependencies: Vec<(String, String)> = cargo_toml_manifest
-            .dependencies
+        let tables = [
+            ("dependencies", &cargo_toml_manifest.dependencies),
+            ("dev-dependencies", &cargo_toml_manifest.dev_dependencies),
+            (
+                "build-dependencies",
+                &cargo_toml_manifest.build_dependencies,
+            ),
+        ];
+        let dependencies: Vec<(String, String, &'static str)> = tables
             .iter()
-            .chain(cargo_toml_manifest.dev_dependencies.iter())
-            .chain(cargo_toml_manifest.build_dependencies.iter())
-            .map(|(name, requirement)| (name.to_string(), requirement.req().to_string()))
+            .flat_map(|(table, dependencies)| {
+                dependencies.iter().map(move |(name, requirement)| {
+                    (name.to_string(), requirement.req().to_string(), *table)
+                })
+            })
             .collect();
         Manifest { dependencies }
     }
@@ -25,7 +34,7 @@ pub struct Cargolock {
 
 impl From<Cargolock> for Lockfile {
     fn from(cargo_lock: Cargolock) -> Lockfile {
-        let packages: Vec<toml::Value> = cargo_lock.package.into();
+        let packages: Vec<toml::Value> = cargo_lock.package;
         let dependencies: HashMap<_, _> = packages
             .iter()
             .map(|p| {
@@ -44,6 +53,34 @@ impl From<Cargolock> for Lockfile {
     }
 }
 
+// Crates the manifest depends on by their key in the manifest, along with their package name
+fn declared_crates(manifest: &toml::Value) -> HashMap<String, String> {
+    let tables = ["dependencies", "dev-dependencies", "build-dependencies"];
+    // Platform specific dependencies too, like [target.'cfg(unix)'.dependencies]
+    let targets = manifest
+        .get("target")
+        .and_then(|targets| targets.as_table())
+        .into_iter()
+        .flat_map(|targets| targets.values());
+    Some(manifest)
+        .into_iter()
+        .chain(targets)
+        .flat_map(|scope| {
+            tables
+                .iter()
+                .filter_map(move |table| scope.get(table)?.as_table())
+        })
+        .flatten()
+        .map(|(name, dependency)| {
+            let package = dependency
+                .get("package")
+                .and_then(|package| package.as_str())
+                .unwrap_or(name);
+            (name.to_string(), package.to_string())
+        })
+        .collect()
+}
+
 pub struct CargoParser;
 
 impl Parser for CargoParser {
@@ -51,8 +88,57 @@ impl Parser for CargoParser {
         Ok(cargo_toml::Manifest::from_str(manifest_content)?.into())
     }
 
+    // Inherited from the workspace when it's a table, which we don't follow
+    fn get_toolchain(manifest_content: &str) -> Option<String> {
+        let manifest: toml::Value = toml::from_str(manifest_content).ok()?;
+        Some(
+            manifest
+                .get("package")?
+                .get("rust-version")?
+                .as_str()?
+                .to_string(),
+        )
+    }
+
+    /* Dependencies of the manifest's own package in the lockfile, written as name,
+     * "name version" or "name version (source)" when several versions are locked
+     */
+    fn get_unused(manifest_content: &str, lockfile_content: &str) -> Vec<String> {
+        let manifest: toml::Value = match toml::from_str(manifest_content) {
+            Ok(manifest) => manifest,
+            Err(_) => return vec![],
+        };
+        let cargo_lock: Cargolock = match toml::from_str(lockfile_content) {
+            Ok(cargo_lock) => cargo_lock,
+            Err(_) => return vec![],
+        };
+        // Virtual workspace manifests have no package of their own
+        let name = match manifest
+            .get("package")
+            .and_then(|package| package.get("name"))
+            .and_then(|name| name.as_str())
+        {
+            Some(name) => name,
+            None => return vec![],
+        };
+        let declared = declared_crates(&manifest);
+        cargo_lock
+            .package
+            .iter()
+            // Path packages have no source, the registry ones may share the name
+            .filter(|package| package.get("source").is_none())
+            .find(|package| package.get("name").and_then(|name| name.as_str()) == Some(name))
+            .and_then(|package| package.get("dependencies")?.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|dependency| dependency.as_str()?.split_whitespace().next())
+            .filter(|dependency| !declared.values().any(|declared| declared == dependency))
+            .map(|dependency| dependency.to_string())
+            .collect()
+    }
+
     fn parse_lockfile(lockfile_content: &str) -> Result<Lockfile, Error> {
-        if lockfile_content == "" {
+        if lockfile_content.is_empty() {
             return Ok(Lockfile {
                 dependencies: HashMap::new(),
             });
@@ -67,38 +153,48 @@ impl Parser for CargoParser {
     ) -> Result<Vec<DependencyInfo>, Error> {
         let cargo_toml = Self::parse_manifest(manifest_content)?;
         let cargo_lock = Self::parse_lockfile(lockfile_content)?;
+        // Renamed dependencies are locked and published under their package name
+        let declared = toml::from_str(manifest_content)
+            .map(|manifest| declared_crates(&manifest))
+            .unwrap_or_default();
 
         // Concatenate all dependencie so we can parallelize network calls
-        Ok(cargo_toml
+        let mut dependencies: Vec<DependencyInfo> = cargo_toml
             .dependencies
             .iter()
-            .map(|(name, requirement)| {
-                let mut line_number: i64 = 0;
-                for (index, line) in manifest_content.split("\n").enumerate() {
-                    if line.to_string().starts_with(&format!("{} = ", name)) {
-                        line_number = index as i64
-                    }
-                }
+            .map(|(key, requirement, table)| {
+                let line_number = toml_line_number(manifest_content, table, key);
+                let name = declared.get(key).unwrap_or(key);
                 if let Some(version) = cargo_lock.dependencies.get(name) {
                     DependencyInfo {
                         line_number,
                         requirement: requirement.to_string(),
                         name: name.to_string(),
                         current: version.to_string(),
-                        // latest: vec![(" ...".to_string(), consts::GREY_HG.to_string())],
-                        latest: vec![(" ".to_string(), consts::GREY_HG.to_string())],
+                        latest: vec![(" ".to_string(), consts::UP_TO_DATE_HG.to_string())],
+                        update: None,
+                        license: None,
+                        released: None,
+                        last_release: None,
                     }
                 } else {
                     DependencyInfo {
                         line_number,
                         requirement: requirement.to_string(),
                         name: name.to_string(),
-                        current: "0.0.0".to_string(),
-                        // latest: vec![(" ...".to_string(), consts::GREY_HG.to_string())],
-                        latest: vec![(" ".to_string(), consts::GREY_HG.to_string())],
+                        // Not locked yet, cargo has nothing installed to fall back on
+                        current: String::new(),
+                        latest: vec![(" ".to_string(), consts::UP_TO_DATE_HG.to_string())],
+                        update: None,
+                        license: None,
+                        released: None,
+                        last_release: None,
                     }
                 }
             })
-            .collect())
+            .collect();
+        // Manifest order, tables are maps so their iteration order can't be relied on
+        dependencies.sort_by(|a, b| (a.line_number, &a.name).cmp(&(b.line_number, &b.name)));
+        Ok(dependencies)
     }
 }
@@ -9,15 +9,28 @@ pub use pipfile::PipfileParser;
 use crate::neovim::DependencyInfo;
 use failure::Error;
 use std::collections::HashMap;
+use std::path::Path;
 
 pub struct Manifest {
-    dependencies: Vec<(String, String)>,
+    // Name, requirement and the table the dependency is declared in
+    dependencies: Vec<(String, String, &'static str)>,
 }
 
 pub struct Lockfile {
     dependencies: HashMap<String, String>,
 }
 
+// A way the lockfile no longer matches the manifest, it needs to be generated again
+#[derive(Debug, PartialEq)]
+pub enum Drift {
+    // A manifest dependency the lockfile has no entry for
+    Unlocked { name: String, line_number: i64 },
+    // A top-level lockfile entry for a dependency the manifest doesn't declare anymore
+    Unused(String),
+    // The lockfile was generated from another version of the manifest, for lockfiles keeping its hash
+    HashMismatch,
+}
+
 pub trait Parser {
     fn get_dependencies(
         manifest_content: &str,
@@ -25,4 +38,95 @@ pub trait Parser {
     ) -> Result<Vec<DependencyInfo>, Error>;
     fn parse_manifest(manifest_content: &str) -> Result<Manifest, Error>;
     fn parse_lockfile(lockfile_content: &str) -> Result<Lockfile, Error>;
+
+    // Version of the toolchain the manifest says it builds with, like the rust-version of a crate
+    fn get_toolchain(_manifest_content: &str) -> Option<String> {
+        None
+    }
+
+    /* Without a lockfile, the versions installed next to the manifest are the next best thing.
+     * Dependencies that aren't installed either keep an empty version
+     */
+    fn resolve_installed(_dependencies: &mut [DependencyInfo], _manifest_dir: &Path) {}
+
+    // Names the lockfile locks for the manifest, that the manifest doesn't declare
+    fn get_unused(_manifest_content: &str, _lockfile_content: &str) -> Vec<String> {
+        vec![]
+    }
+
+    // Whether the lockfile records a hash of the manifest, and it's not the current one
+    fn is_hash_mismatch(_manifest_content: &str, _lockfile_content: &str) -> bool {
+        false
+    }
+
+    /* Compare the manifest with its lockfile, None without one.
+     * Locked versions not matching their requirement are checked by the stores,
+     * they know how each ecosystem reads requirements
+     */
+    fn get_drift(
+        manifest_content: &str,
+        lockfile_content: &str,
+    ) -> Result<Option<Vec<Drift>>, Error> {
+        if lockfile_content.trim().is_empty() {
+            return Ok(None);
+        }
+        let mut drift: Vec<Drift> = vec![];
+        if Self::is_hash_mismatch(manifest_content, lockfile_content) {
+            drift.push(Drift::HashMismatch);
+        }
+        for dep in Self::get_dependencies(manifest_content, lockfile_content)? {
+            if dep.current.is_empty() {
+                drift.push(Drift::Unlocked {
+                    name: dep.name,
+                    line_number: dep.line_number,
+                });
+            }
+        }
+        let mut unused = Self::get_unused(manifest_content, lockfile_content);
+        unused.sort();
+        unused.dedup();
+        drift.extend(unused.into_iter().map(Drift::Unused));
+        Ok(Some(drift))
+    }
+}
+
+// Strip the quotes around a TOML key
+fn unquote(key: &str) -> &str {
+    key.trim().trim_matches(['"', '\''])
+}
+
+/* Line of a dependency declared in a TOML table, either as a key of the table
+ * (possibly dotted or quoted) or as a table of its own, like [dependencies.serde]
+ */
+fn toml_line_number(manifest_content: &str, table: &str, name: &str) -> i64 {
+    let mut in_table = false;
+    for (index, line) in manifest_content.lines().enumerate() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            let header = line.trim_matches(['[', ']']);
+            let keys: Vec<&str> = header.split('.').map(unquote).collect();
+            if keys.len() > 1 && keys[keys.len() - 2] == table && keys[keys.len() - 1] == name {
+                return index as i64;
+            }
+            in_table = keys.last() == Some(&table);
+        } else if in_table {
+            let key = line.split('=').next().unwrap_or("");
+            if line.contains('=') && unquote(key.split('.').next().unwrap_or("")) == name {
+                return index as i64;
+            }
+        }
+    }
+    0
+}
+
+// Line of a dependency declared in a JSON object, looked up after the object key
+fn json_line_number(manifest_content: &str, table: &str, name: &str) -> i64 {
+    let table = format!("\"{}\":", table);
+    let name = format!("\"{}\":", name);
+    manifest_content
+        .lines()
+        .enumerate()
+        .skip_while(|(_, line)| !line.contains(&table))
+        .find(|(_, line)| line.contains(&name))
+        .map_or(0, |(index, _)| index as i64)
 }
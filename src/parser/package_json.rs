@@ -1,14 +1,17 @@
 use crate::consts;
 use crate::neovim::DependencyInfo;
-use crate::parser::{Lockfile, Manifest, Parser};
+use crate::parser::{json_line_number, Lockfile, Manifest, Parser};
 use failure::Error;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
 
 #[derive(Serialize, Deserialize)]
 pub struct PackageJson {
+    #[serde(default)]
     pub dependencies: HashMap<String, String>,
-    #[serde(rename = "devDependencies")]
+    #[serde(rename = "devDependencies", default)]
     pub dev_dependencies: HashMap<String, String>,
 }
 
@@ -69,13 +72,71 @@ impl YarnLock {
     }
 }
 
+// Package name of a descriptor like "@babel/core@^7.4.5", the scope starts with an @ too
+fn descriptor_name(descriptor: &str) -> &str {
+    let start = usize::from(descriptor.starts_with('@'));
+    match descriptor[start..].find('@') {
+        Some(index) => &descriptor[..start + index],
+        None => descriptor,
+    }
+}
+
+/* Descriptors of the yarn.lock entries nothing else in the lockfile depends on,
+ * the ones added for the manifest. Entries start with their comma separated descriptors,
+ * the dependencies of each entry are listed in it as name "range"
+ */
+fn top_level_descriptors(lockfile_content: &str) -> Vec<String> {
+    let mut descriptors: Vec<String> = vec![];
+    let mut dependencies: Vec<String> = vec![];
+    let mut in_dependencies = false;
+    for line in lockfile_content.lines() {
+        let indent = line.len() - line.trim_start().len();
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match indent {
+            // Yarn 2+ lockfiles have metadata and the workspace itself as entries too
+            0 if line.starts_with("__metadata") || line.contains("@workspace:") => {
+                in_dependencies = false;
+            }
+            0 => {
+                in_dependencies = false;
+                descriptors.extend(
+                    line.trim_end_matches(':')
+                        .split(", ")
+                        .map(|descriptor| descriptor.trim_matches('"').to_string()),
+                );
+            }
+            2 => in_dependencies = line == "dependencies:" || line == "optionalDependencies:",
+            _ if in_dependencies => {
+                let (name, range) = line.split_once(' ').unwrap_or((line, ""));
+                dependencies.push(format!(
+                    "{}@{}",
+                    name.trim_end_matches(':').trim_matches('"'),
+                    range.trim().trim_matches('"')
+                ));
+            }
+            _ => (),
+        }
+    }
+    descriptors.retain(|descriptor| !dependencies.contains(descriptor));
+    descriptors
+}
+
 impl From<PackageJson> for Manifest {
     fn from(package_json: PackageJson) -> Manifest {
-        let dependencies: Vec<(String, String)> = package_json
+        let dependencies: Vec<(String, String, &'static str)> = package_json
             .dependencies
             .iter()
-            .chain(package_json.dev_dependencies.iter())
-            .map(|(name, requirement)| (name.to_string(), requirement.to_string()))
+            .map(|(name, requirement)| (name, requirement, "dependencies"))
+            .chain(
+                package_json
+                    .dev_dependencies
+                    .iter()
+                    .map(|(name, requirement)| (name, requirement, "devDependencies")),
+            )
+            .map(|(name, requirement, table)| (name.to_string(), requirement.to_string(), table))
             .collect();
         Manifest { dependencies }
     }
@@ -99,6 +160,51 @@ impl Parser for PackageJsonParser {
         Ok(PackageJson::from_str(manifest_content)?.into())
     }
 
+    // The node versions the package says it runs on
+    fn get_toolchain(manifest_content: &str) -> Option<String> {
+        let package_json: serde_json::Value = serde_json::from_str(manifest_content).ok()?;
+        Some(package_json["engines"]["node"].as_str()?.to_string())
+    }
+
+    // The version in the package.json of each package in node_modules
+    fn resolve_installed(dependencies: &mut [DependencyInfo], manifest_dir: &Path) {
+        let node_modules = manifest_dir.join("node_modules");
+        for dep in dependencies.iter_mut().filter(|dep| dep.current.is_empty()) {
+            let package_json = node_modules.join(&dep.name).join("package.json");
+            let version = fs::read_to_string(package_json)
+                .ok()
+                .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+                .and_then(|package_json| Some(package_json["version"].as_str()?.to_string()));
+            if let Some(version) = version {
+                dep.current = version;
+            }
+        }
+    }
+
+    fn get_unused(manifest_content: &str, lockfile_content: &str) -> Vec<String> {
+        let package_json: serde_json::Value = match serde_json::from_str(manifest_content) {
+            Ok(package_json) => package_json,
+            Err(_) => return vec![],
+        };
+        let tables = [
+            "dependencies",
+            "devDependencies",
+            "optionalDependencies",
+            "peerDependencies",
+        ];
+        let declared: Vec<&String> = tables
+            .iter()
+            .filter_map(|table| package_json[table].as_object())
+            .flat_map(|dependencies| dependencies.keys())
+            .collect();
+        top_level_descriptors(lockfile_content)
+            .iter()
+            .map(|descriptor| descriptor_name(descriptor))
+            .filter(|name| !declared.iter().any(|declared| declared == name))
+            .map(|name| name.to_string())
+            .collect()
+    }
+
     fn parse_lockfile(lockfile_content: &str) -> Result<Lockfile, Error> {
         let lock_file = YarnLock::from_str(lockfile_content)?;
         Ok(lock_file.into())
@@ -117,16 +223,11 @@ impl Parser for PackageJsonParser {
         };
 
         // Concatenate all dependencie so we can parallelize network calls
-        Ok(package_json
+        let mut dependencies: Vec<DependencyInfo> = package_json
             .dependencies
             .iter()
-            .map(|(name, requirement)| {
-                let mut line_number: i64 = 0;
-                for (index, line) in manifest_content.split("\n").enumerate() {
-                    if line.to_string().contains(&format!("\"{}\": \"", name)) {
-                        line_number = index as i64
-                    }
-                }
+            .map(|(name, requirement, table)| {
+                let line_number = json_line_number(manifest_content, table, name);
                 if let Some(version) = yarn_lock.dependencies.get(name) {
                     let mut v = version.chars();
                     v.next();
@@ -135,18 +236,30 @@ impl Parser for PackageJsonParser {
                         requirement: requirement.to_string(),
                         name: name.to_string(),
                         current: v.as_str().to_string(),
-                        latest: vec![(" ".to_string(), consts::GREY_HG.to_string())],
+                        latest: vec![(" ".to_string(), consts::UP_TO_DATE_HG.to_string())],
+                        update: None,
+                        license: None,
+                        released: None,
+                        last_release: None,
                     }
                 } else {
                     DependencyInfo {
                         line_number,
                         requirement: requirement.to_string(),
                         name: name.to_string(),
-                        current: "0.0.0".to_string(),
-                        latest: vec![(" ".to_string(), consts::GREY_HG.to_string())],
+                        // Not locked, filled from the installed packages when possible
+                        current: String::new(),
+                        latest: vec![(" ".to_string(), consts::UP_TO_DATE_HG.to_string())],
+                        update: None,
+                        license: None,
+                        released: None,
+                        last_release: None,
                     }
                 }
             })
-            .collect())
+            .collect();
+        // Manifest order, tables are maps so their iteration order can't be relied on
+        dependencies.sort_by(|a, b| (a.line_number, &a.name).cmp(&(b.line_number, &b.name)));
+        Ok(dependencies)
     }
 }
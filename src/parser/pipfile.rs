@@ -1,22 +1,28 @@
 use crate::consts;
 use crate::neovim::DependencyInfo;
-use crate::parser::{Lockfile, Manifest, Parser};
+use crate::parser::{toml_line_number, Lockfile, Manifest, Parser};
+use crate::python::normalize_name;
 use failure::Error;
 use serde::{Deserialize, Serialize};
-use serde_json;
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 #[derive(Serialize, Deserialize)]
 pub struct Pipfile {
     #[serde(rename = "packages")]
     pub dependencies: HashMap<String, toml::Value>,
-    #[serde(rename = "dev-packages")]
+    #[serde(rename = "dev-packages", default)]
     pub dev_dependencies: HashMap<String, toml::Value>,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct Piplock {
+    #[serde(default)]
     pub default: HashMap<String, serde_json::Value>,
+    #[serde(default)]
     pub develop: HashMap<String, serde_json::Value>,
 }
 
@@ -24,6 +30,18 @@ impl Pipfile {
     pub fn from_str(content: &str) -> Result<Self, Error> {
         Ok(toml::from_str(content)?)
     }
+
+    // Either a plain requirement, or a table where the version is optional
+    fn requirement(value: &toml::Value) -> String {
+        match value {
+            toml::Value::String(requirement) => requirement.to_string(),
+            _ => value
+                .get("version")
+                .and_then(|version| version.as_str())
+                .unwrap_or("*")
+                .to_string(),
+        }
+    }
 }
 
 impl Piplock {
@@ -34,11 +52,19 @@ impl Piplock {
 
 impl From<Pipfile> for Manifest {
     fn from(pipfile: Pipfile) -> Manifest {
-        let dependencies: Vec<(String, String)> = pipfile
+        let dependencies: Vec<(String, String, &'static str)> = pipfile
             .dependencies
             .iter()
-            .chain(pipfile.dev_dependencies.iter())
-            .map(|(name, requirement)| (name.to_string(), requirement.to_string()))
+            .map(|(name, requirement)| (name, requirement, "packages"))
+            .chain(
+                pipfile
+                    .dev_dependencies
+                    .iter()
+                    .map(|(name, requirement)| (name, requirement, "dev-packages")),
+            )
+            .map(|(name, requirement, table)| {
+                (name.to_string(), Pipfile::requirement(requirement), table)
+            })
             .collect();
         Manifest { dependencies }
     }
@@ -50,17 +76,155 @@ impl From<Piplock> for Lockfile {
             .default
             .iter()
             .chain(piplock.develop.iter())
-            .map(|dep| {
-                (
-                    dep.0.to_string(),
-                    dep.1["version"].as_str().expect("").to_string(),
-                )
+            // Packages from git or a path have no version
+            .filter_map(|(name, package)| {
+                Some((normalize_name(name), package["version"].as_str()?))
             })
+            .map(|(name, version)| (name, version.to_string()))
             .collect();
         Lockfile { dependencies }
     }
 }
 
+/* The site-packages of the active virtualenv, or of the one pipenv keeps in the project
+ * with PIPENV_VENV_IN_PROJECT. Posix virtualenvs have one per python version
+ */
+fn site_packages(manifest_dir: &Path) -> Vec<PathBuf> {
+    let virtualenv = match env::var_os("VIRTUAL_ENV") {
+        Some(virtualenv) => PathBuf::from(virtualenv),
+        None => manifest_dir.join(".venv"),
+    };
+    let mut site_packages: Vec<PathBuf> = fs::read_dir(virtualenv.join("lib"))
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| Some(entry.ok()?.path().join("site-packages")))
+        .chain(Some(virtualenv.join("Lib").join("site-packages")))
+        .filter(|path| path.is_dir())
+        .collect();
+    site_packages.sort();
+    site_packages
+}
+
+// Normalized name and version of each distribution, from the headers of their METADATA
+fn installed_distributions(site_packages: &Path) -> HashMap<String, String> {
+    fs::read_dir(site_packages)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "dist-info")
+        })
+        .filter_map(|path| {
+            let metadata = fs::read_to_string(path.join("METADATA")).ok()?;
+            // Headers end at the first empty line, the description follows
+            let headers = metadata.lines().take_while(|line| !line.is_empty());
+            let (mut name, mut version) = (None, None);
+            for (key, value) in headers.filter_map(|line| line.split_once(':')) {
+                match key {
+                    "Name" => name = Some(normalize_name(value.trim())),
+                    "Version" => version = Some(value.trim().to_string()),
+                    _ => (),
+                }
+            }
+            Some((name?, version?))
+        })
+        .collect()
+}
+
+// Python's json.dumps, with sorted keys, compact separators and non ASCII characters escaped
+fn python_json(value: &serde_json::Value, json: &mut String) {
+    match value {
+        serde_json::Value::String(string) => {
+            json.push('"');
+            for c in string.chars() {
+                match c {
+                    '"' => json.push_str("\\\""),
+                    '\\' => json.push_str("\\\\"),
+                    '\n' => json.push_str("\\n"),
+                    '\r' => json.push_str("\\r"),
+                    '\t' => json.push_str("\\t"),
+                    '\u{8}' => json.push_str("\\b"),
+                    '\u{c}' => json.push_str("\\f"),
+                    ' '..='~' => json.push(c),
+                    _ => {
+                        let mut units = [0; 2];
+                        for unit in c.encode_utf16(&mut units) {
+                            json.push_str(&format!("\\u{:04x}", unit));
+                        }
+                    }
+                }
+            }
+            json.push('"');
+        }
+        serde_json::Value::Array(values) => {
+            json.push('[');
+            for (index, value) in values.iter().enumerate() {
+                if index > 0 {
+                    json.push(',');
+                }
+                python_json(value, json);
+            }
+            json.push(']');
+        }
+        serde_json::Value::Object(map) => {
+            let mut entries: Vec<(&String, &serde_json::Value)> = map.iter().collect();
+            entries.sort_by_key(|(key, _)| *key);
+            json.push('{');
+            for (index, (key, value)) in entries.into_iter().enumerate() {
+                if index > 0 {
+                    json.push(',');
+                }
+                python_json(&serde_json::Value::String(key.to_string()), json);
+                json.push(':');
+                python_json(value, json);
+            }
+            json.push('}');
+        }
+        // Numbers, booleans and null are written the same way
+        value => json.push_str(&value.to_string()),
+    }
+}
+
+/* The hash pipenv keeps in Pipfile.lock, of the Pipfile sections that affect locking.
+ * Sources default to PyPI, and custom package categories are part of it too
+ */
+fn pipfile_hash(pipfile: &toml::Value) -> Option<String> {
+    let pipfile = serde_json::to_value(pipfile).ok()?;
+    let pipfile = pipfile.as_object()?;
+    let section = |name: &str| {
+        pipfile
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| serde_json::json!({}))
+    };
+    let sources = pipfile.get("source").cloned().unwrap_or_else(|| {
+        serde_json::json!([{ "name": "pypi", "url": "https://pypi.org/simple", "verify_ssl": true }])
+    });
+    let mut data = serde_json::json!({
+        "_meta": { "requires": section("requires"), "sources": sources },
+        "default": section("packages"),
+        "develop": section("dev-packages"),
+    });
+    let sections = [
+        "source",
+        "packages",
+        "dev-packages",
+        "requires",
+        "scripts",
+        "pipfile",
+        "pipenv",
+    ];
+    for (name, category) in pipfile {
+        if !sections.contains(&name.as_str()) {
+            data[name] = category.clone();
+        }
+    }
+    let mut json = String::new();
+    python_json(&data, &mut json);
+    Some(format!("{:x}", Sha256::digest(json.as_bytes())))
+}
+
 pub struct PipfileParser;
 
 impl Parser for PipfileParser {
@@ -68,6 +232,43 @@ impl Parser for PipfileParser {
         Ok(Pipfile::from_str(manifest_content)?.into())
     }
 
+    // The exact python_full_version when given, python_version otherwise
+    fn get_toolchain(manifest_content: &str) -> Option<String> {
+        let pipfile: toml::Value = toml::from_str(manifest_content).ok()?;
+        let requires = pipfile.get("requires")?;
+        let version = requires
+            .get("python_full_version")
+            .or_else(|| requires.get("python_version"))?;
+        Some(version.as_str()?.to_string())
+    }
+
+    fn resolve_installed(dependencies: &mut [DependencyInfo], manifest_dir: &Path) {
+        let installed: HashMap<String, String> = site_packages(manifest_dir)
+            .iter()
+            .flat_map(|site_packages| installed_distributions(site_packages))
+            .collect();
+        for dep in dependencies.iter_mut().filter(|dep| dep.current.is_empty()) {
+            if let Some(version) = installed.get(&normalize_name(&dep.name)) {
+                dep.current = version.to_string();
+            }
+        }
+    }
+
+    fn is_hash_mismatch(manifest_content: &str, lockfile_content: &str) -> bool {
+        let piplock: serde_json::Value = match serde_json::from_str(lockfile_content) {
+            Ok(piplock) => piplock,
+            Err(_) => return false,
+        };
+        let locked = piplock["_meta"]["hash"]["sha256"].as_str();
+        let hash = toml::from_str(manifest_content)
+            .ok()
+            .and_then(|pipfile| pipfile_hash(&pipfile));
+        match (locked, hash) {
+            (Some(locked), Some(hash)) => locked != hash,
+            _ => false,
+        }
+    }
+
     fn parse_lockfile(lockfile_content: &str) -> Result<Lockfile, Error> {
         Ok(Piplock::from_str(lockfile_content)?.into())
     }
@@ -85,19 +286,12 @@ impl Parser for PipfileParser {
         };
 
         // Concatenate all dependencie so we can parallelize network calls
-        Ok(pipfile
+        let mut dependencies: Vec<DependencyInfo> = pipfile
             .dependencies
             .iter()
-            .map(|(name, requirement)| {
-                let mut line_number: i64 = 0;
-                for (index, line) in manifest_content.split("\n").enumerate() {
-                    if line.to_string().starts_with(&format!("{} = ", name))
-                        || line.to_string().starts_with(&format!("\"{}\" = ", name))
-                    {
-                        line_number = index as i64
-                    }
-                }
-                if let Some(version) = piplock.dependencies.get(name) {
+            .map(|(name, requirement, table)| {
+                let line_number = toml_line_number(manifest_content, table, name);
+                if let Some(version) = piplock.dependencies.get(&normalize_name(name)) {
                     let mut v = version.chars();
                     v.next();
                     v.next();
@@ -106,20 +300,30 @@ impl Parser for PipfileParser {
                         requirement: requirement.to_string(),
                         name: name.to_string(),
                         current: v.as_str().to_string(),
-                        // latest: vec![(" ...".to_string(), consts::GREY_HG.to_string())],
-                        latest: vec![(" ".to_string(), consts::GREY_HG.to_string())],
+                        latest: vec![(" ".to_string(), consts::UP_TO_DATE_HG.to_string())],
+                        update: None,
+                        license: None,
+                        released: None,
+                        last_release: None,
                     }
                 } else {
                     DependencyInfo {
                         line_number,
                         requirement: requirement.to_string(),
                         name: name.to_string(),
-                        current: "0.0.0".to_string(),
-                        // latest: vec![(" ...".to_string(), consts::GREY_HG.to_string())],
-                        latest: vec![(" ".to_string(), consts::GREY_HG.to_string())],
+                        // Not locked, filled from the installed packages when possible
+                        current: String::new(),
+                        latest: vec![(" ".to_string(), consts::UP_TO_DATE_HG.to_string())],
+                        update: None,
+                        license: None,
+                        released: None,
+                        last_release: None,
                     }
                 }
             })
-            .collect())
+            .collect();
+        // Manifest order, tables are maps so their iteration order can't be relied on
+        dependencies.sort_by(|a, b| (a.line_number, &a.name).cmp(&(b.line_number, &b.name)));
+        Ok(dependencies)
     }
 }
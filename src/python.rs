@@ -0,0 +1,169 @@
+use std::str::FromStr;
+
+// Pipfile.lock and the advisory databases use normalized names, see PEP 503
+pub fn normalize_name(name: &str) -> String {
+    name.to_lowercase()
+        .split(['-', '_', '.'])
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<&str>>()
+        .join("-")
+}
+
+/* A version ordered as PEP 440 says, local versions (after a +) are ignored.
+ * Fields are compared in order, so they hold sort keys rather than what was parsed:
+ * a development release of 1.0 comes before its pre-releases, which come before 1.0,
+ * which comes before its post-releases.
+ */
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Version {
+    epoch: u64,
+    // Without trailing zeros, 1.0 and 1.0.0 are the same version
+    release: Vec<u64>,
+    // 0 for development releases, 1 to 3 for alpha, beta and release candidates, 4 otherwise
+    pre: (u8, u64),
+    post: Option<u64>,
+    // Development releases come before the version they lead to
+    dev: (u8, u64),
+}
+
+impl Version {
+    pub fn parse(version: &str) -> Option<Self> {
+        version.parse().ok()
+    }
+
+    pub fn is_prerelease(&self) -> bool {
+        self.pre.0 < 4 || self.dev.0 == 0
+    }
+
+    // Whether the release starts with the given numbers, missing ones being zeros
+    fn has_prefix(&self, prefix: &[u64]) -> bool {
+        prefix
+            .iter()
+            .enumerate()
+            .all(|(index, part)| self.release.get(index).unwrap_or(&0) == part)
+    }
+
+    /* Whether the version satisfies a comma separated list of specifiers, like the
+     * requires_python of a release. None if a specifier can't be parsed
+     */
+    pub fn satisfies(&self, specifiers: &str) -> Option<bool> {
+        for specifier in specifiers.split(',').map(|specifier| specifier.trim()) {
+            if specifier.is_empty() {
+                continue;
+            }
+            let (operator, version) = take_while(specifier, |c| "<>=!~".contains(c));
+            let version = version.trim();
+            let satisfied = match (operator, version.strip_suffix(".*")) {
+                ("===", _) => self == &Version::parse(version)?,
+                ("==", Some(prefix)) | ("!=", Some(prefix)) => {
+                    let prefix = release_numbers(prefix)?;
+                    self.has_prefix(&prefix) == (operator == "==")
+                }
+                ("~=", None) => {
+                    // ~= 3.8.1 is >= 3.8.1 and == 3.8.*
+                    let mut prefix = release_numbers(version)?;
+                    prefix.pop();
+                    !prefix.is_empty()
+                        && self >= &Version::parse(version)?
+                        && self.has_prefix(&prefix)
+                }
+                (operator, None) => {
+                    let version = Version::parse(version)?;
+                    match operator {
+                        "==" => *self == version,
+                        "!=" => *self != version,
+                        ">=" => *self >= version,
+                        "<=" => *self <= version,
+                        ">" => *self > version,
+                        "<" => *self < version,
+                        _ => return None,
+                    }
+                }
+                _ => return None,
+            };
+            if !satisfied {
+                return Some(false);
+            }
+        }
+        Some(true)
+    }
+}
+
+// The release numbers of a version, as written
+fn release_numbers(version: &str) -> Option<Vec<u64>> {
+    let version = version.split('!').next_back()?;
+    let (release, _) = take_while(version, |c| c.is_ascii_digit() || c == '.');
+    release
+        .trim_end_matches('.')
+        .split('.')
+        .map(|part| part.parse().ok())
+        .collect()
+}
+
+// Split the leading run of characters matching the predicate
+fn take_while(input: &str, predicate: impl Fn(char) -> bool) -> (&str, &str) {
+    let end = input.find(|c| !predicate(c)).unwrap_or(input.len());
+    input.split_at(end)
+}
+
+impl FromStr for Version {
+    type Err = ();
+
+    fn from_str(version: &str) -> Result<Self, ()> {
+        let version = version.trim().to_lowercase();
+        let version = version.trim_start_matches('v');
+        let version = version.split('+').next().unwrap_or("");
+        let (epoch, version) = match version.split_once('!') {
+            Some((epoch, version)) => (epoch.parse().map_err(|_| ())?, version),
+            None => (0, version),
+        };
+
+        let (release, mut rest) = take_while(version, |c| c.is_ascii_digit() || c == '.');
+        let mut release: Vec<u64> = release
+            .trim_end_matches('.')
+            .split('.')
+            .map(|part| part.parse().map_err(|_| ()))
+            .collect::<Result<_, _>>()?;
+        while release.len() > 1 && release.last() == Some(&0) {
+            release.pop();
+        }
+
+        let (mut pre, mut post, mut dev) = (None, None, None);
+        while !rest.is_empty() {
+            let implicit_post = rest.starts_with('-');
+            rest = rest.trim_start_matches(['-', '_', '.']);
+            let (word, remaining) = take_while(rest, |c| c.is_ascii_alphabetic());
+            let (number, remaining) =
+                take_while(remaining.trim_start_matches(['-', '_', '.']), |c| {
+                    c.is_ascii_digit()
+                });
+            if word.is_empty() && number.is_empty() {
+                return Err(());
+            }
+            let number: u64 = number.parse().unwrap_or(0);
+            match word {
+                "a" | "alpha" => pre = Some((1, number)),
+                "b" | "beta" => pre = Some((2, number)),
+                "c" | "rc" | "pre" | "preview" => pre = Some((3, number)),
+                "post" | "rev" | "r" => post = Some(number),
+                "" if implicit_post => post = Some(number),
+                "dev" => dev = Some(number),
+                _ => return Err(()),
+            }
+            rest = remaining;
+        }
+
+        let pre = match (pre, post, dev) {
+            (Some(pre), _, _) => pre,
+            (None, None, Some(_)) => (0, 0),
+            _ => (4, 0),
+        };
+        Ok(Version {
+            epoch,
+            release,
+            pre,
+            post,
+            dev: dev.map_or((1, 0), |dev| (0, dev)),
+        })
+    }
+}
@@ -0,0 +1,108 @@
+use crate::neovim::Config;
+use crate::store::StoreError;
+
+use reqwest::header::{HeaderMap, HeaderValue, USER_AGENT};
+use reqwest::{Certificate, Client, Proxy, Url};
+use std::env;
+use std::fs;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+// The client is built once and shared by all the stores, until its settings change
+static CLIENT: OnceLock<Mutex<Option<(Settings, Client)>>> = OnceLock::new();
+
+#[derive(Clone, PartialEq)]
+struct Settings {
+    timeout: u64,
+    ca_bundle: Option<String>,
+    user_agent: String,
+}
+
+impl Settings {
+    fn new(config: &Config) -> Self {
+        // Registries like crates.io ask for a user agent telling who we are
+        let contact = config
+            .user_agent
+            .clone()
+            .unwrap_or_else(|| "https://github.com/Psykopear/neovim-package-info".to_string());
+        Settings {
+            timeout: config.timeout,
+            ca_bundle: config.ca_bundle.clone(),
+            user_agent: format!(
+                "neovim-package-info/{} ({})",
+                env!("CARGO_PKG_VERSION"),
+                contact
+            ),
+        }
+    }
+}
+
+pub fn get_client(config: &Config) -> Result<Client, StoreError> {
+    let settings = Settings::new(config);
+    let mut shared = CLIENT.get_or_init(|| Mutex::new(None)).lock().unwrap();
+    if let Some((current, client)) = shared.as_ref() {
+        if *current == settings {
+            return Ok(client.clone());
+        }
+    }
+    let client = build(&settings)?;
+    *shared = Some((settings, client.clone()));
+    Ok(client)
+}
+
+fn build(settings: &Settings) -> Result<Client, StoreError> {
+    let mut headers = HeaderMap::new();
+    let user_agent = HeaderValue::from_str(&settings.user_agent)
+        .map_err(|error| StoreError::Network(error.to_string()))?;
+    headers.insert(USER_AGENT, user_agent);
+
+    let mut builder = Client::builder()
+        .timeout(Duration::from_secs(settings.timeout))
+        .default_headers(headers)
+        .proxy(Proxy::custom(proxy_for));
+    if let Some(ca_bundle) = &settings.ca_bundle {
+        for certificate in read_certificates(ca_bundle)? {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+    Ok(builder.build()?)
+}
+
+// A bundle can hold many PEM certificates, which have to be added one by one
+fn read_certificates(path: &str) -> Result<Vec<Certificate>, StoreError> {
+    let bundle = fs::read_to_string(path).map_err(|error| {
+        StoreError::Network(format!("can't read CA bundle {}: {}", path, error))
+    })?;
+    let end = "-----END CERTIFICATE-----";
+    bundle
+        .split_terminator(end)
+        .filter(|pem| pem.contains("-----BEGIN CERTIFICATE-----"))
+        .map(|pem| Ok(Certificate::from_pem(format!("{}{}", pem, end).as_bytes())?))
+        .collect()
+}
+
+fn env_var(name: &str) -> Option<String> {
+    env::var(name.to_uppercase())
+        .or_else(|_| env::var(name))
+        .ok()
+        .filter(|value| !value.is_empty())
+}
+
+// Pick the proxy from HTTPS_PROXY/HTTP_PROXY, unless the host is excluded by NO_PROXY
+fn proxy_for(url: &Url) -> Option<Url> {
+    let host = url.host_str()?;
+    if let Some(no_proxy) = env_var("no_proxy") {
+        let excluded = no_proxy.split(',').map(|entry| entry.trim()).any(|entry| {
+            let domain = entry.trim_start_matches('.');
+            entry == "*" || host == domain || host.ends_with(&format!(".{}", domain))
+        });
+        if excluded {
+            return None;
+        }
+    }
+    let proxy = match url.scheme() {
+        "https" => env_var("https_proxy"),
+        _ => env_var("http_proxy"),
+    };
+    Url::parse(&proxy?).ok()
+}
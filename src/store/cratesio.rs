@@ -1,19 +1,135 @@
-use crate::store::Store;
-use failure::Error;
+use crate::neovim::Config;
+use crate::store::{timestamp, Registry, Store};
 
-pub struct Cratesio;
+use std::collections::HashMap;
+use std::env;
+use std::path::PathBuf;
+
+// Where cargo keeps its registry sources, and cargo-audit the advisory database
+pub fn cargo_home() -> Option<PathBuf> {
+    match env::var_os("CARGO_HOME") {
+        Some(cargo_home) => Some(PathBuf::from(cargo_home)),
+        None => Some(PathBuf::from(env::var_os("HOME")?).join(".cargo")),
+    }
+}
+
+// Rust versions can leave out the patch number, like 1.70
+fn rust_version(version: &str) -> Option<semver::Version> {
+    let mut parts: Vec<&str> = version.trim().split('.').collect();
+    while parts.len() < 3 {
+        parts.push("0");
+    }
+    semver::Version::parse(&parts.join(".")).ok()
+}
+
+pub struct Cratesio {
+    registry: Registry,
+}
+
+impl Cratesio {
+    pub const URL: &'static str = "https://crates.io/api/v1/crates";
+
+    pub fn new(registry: Registry) -> Self {
+        Cratesio { registry }
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        Cratesio::new(Registry::configured("cargo", Self::URL, config))
+    }
+}
 
 impl Store for Cratesio {
-    fn get_url() -> String {
-        "https://crates.io/api/v1/crates/{package}".to_string()
+    fn registry(&self) -> &Registry {
+        &self.registry
     }
 
-    fn get_max_version(package: &str) -> Result<String, Error> {
-        let body = Self::get_package_info(package)?;
+    fn ecosystem(&self) -> &'static str {
+        "cargo"
+    }
+
+    fn parse_max_version(&self, body: &serde_json::Value) -> String {
         if let Some(max_version) = body["crate"]["max_version"].as_str() {
-            Ok(max_version.to_string())
+            max_version.to_string()
         } else {
-            Ok("Can't find version".to_string())
+            "Can't find version".to_string()
+        }
+    }
+
+    fn parse_versions(&self, body: &serde_json::Value) -> Vec<String> {
+        match body["versions"].as_array() {
+            Some(versions) => versions
+                .iter()
+                .filter(|version| !version["yanked"].as_bool().unwrap_or(false))
+                .filter_map(|version| version["num"].as_str())
+                .map(|version| version.to_string())
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    fn parse_released(&self, body: &serde_json::Value) -> HashMap<String, u64> {
+        match body["versions"].as_array() {
+            Some(versions) => versions
+                .iter()
+                .filter_map(|version| {
+                    let num = version["num"].as_str()?;
+                    Some((num.to_string(), timestamp(version["created_at"].as_str()?)?))
+                })
+                .collect(),
+            None => HashMap::new(),
+        }
+    }
+
+    fn parse_toolchains(&self, body: &serde_json::Value) -> HashMap<String, String> {
+        match body["versions"].as_array() {
+            Some(versions) => versions
+                .iter()
+                .filter_map(|version| {
+                    let num = version["num"].as_str()?;
+                    Some((
+                        num.to_string(),
+                        version["rust_version"].as_str()?.to_string(),
+                    ))
+                })
+                .collect(),
+            None => HashMap::new(),
+        }
+    }
+
+    fn toolchain(&self) -> &'static str {
+        "rust"
+    }
+
+    fn parse_repository(&self, body: &serde_json::Value) -> Option<String> {
+        Some(body["crate"]["repository"].as_str()?.to_string())
+    }
+
+    // The .crate file, redirected to the static file server
+    fn get_tarball_url(&self, package: &str, version: &str) -> Option<String> {
+        Some(format!(
+            "{}/{}/{}/download",
+            self.registry.url, package, version
+        ))
+    }
+
+    // A crate's rust_version is the oldest compiler it builds with
+    fn is_compatible(&self, required: &str, toolchain: &str) -> bool {
+        match (rust_version(required), rust_version(toolchain)) {
+            (Some(required), Some(toolchain)) => required <= toolchain,
+            _ => true,
+        }
+    }
+
+    fn parse_licenses(&self, body: &serde_json::Value) -> HashMap<String, String> {
+        match body["versions"].as_array() {
+            Some(versions) => versions
+                .iter()
+                .filter_map(|version| {
+                    let num = version["num"].as_str()?;
+                    Some((num.to_string(), version["license"].as_str()?.to_string()))
+                })
+                .collect(),
+            None => HashMap::new(),
         }
     }
 }
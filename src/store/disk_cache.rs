@@ -0,0 +1,92 @@
+use crate::store::Package;
+
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/* Registry lookups persisted under $XDG_CACHE_HOME/neovim-package-info/<cache key>/,
+ * one json file per package. Files are locked while read or written, since
+ * the cache is shared by every running Neovim instance.
+ */
+pub struct DiskCache {
+    dir: PathBuf,
+    ttl: u64,
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs())
+        .unwrap_or(0)
+}
+
+/* Directory name of a registry url. The path is kept, registries served from the same
+ * host (like the Artifactory ones) get their own directory, and so does the port
+ */
+fn dir_name(registry: &str) -> String {
+    let registry = registry
+        .split_once("://")
+        .map_or(registry, |(_, rest)| rest);
+    registry
+        .replace('%', "%25")
+        .replace('/', "%2F")
+        .replace(':', "%3A")
+        .replace('#', "%23")
+}
+
+impl DiskCache {
+    pub fn new(registry: &str, ttl: u64) -> Option<Self> {
+        let cache_home = match env::var_os("XDG_CACHE_HOME") {
+            Some(cache_home) => PathBuf::from(cache_home),
+            None => PathBuf::from(env::var_os("HOME")?).join(".cache"),
+        };
+        let dir = cache_home
+            .join("neovim-package-info")
+            .join(dir_name(registry));
+        Some(DiskCache { dir, ttl })
+    }
+
+    fn path(&self, package: &str) -> PathBuf {
+        // Scoped npm packages contain a slash
+        self.dir
+            .join(format!("{}.json", package.replace('/', "%2F")))
+    }
+
+    pub fn get(&self, package: &str) -> Option<Package> {
+        let mut file = File::open(self.path(package)).ok()?;
+        file.lock_shared().ok()?;
+        let mut content = String::new();
+        file.read_to_string(&mut content).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    // Stale packages are still useful to revalidate the lookup with the registry
+    pub fn is_fresh(&self, package: &Package) -> bool {
+        now().saturating_sub(package.fetched_at) <= self.ttl
+    }
+
+    pub fn set(&self, package: &str, info: &Package) {
+        // Failing to write the cache only means we will query the registry again
+        let _ = self.write(package, info);
+    }
+
+    fn write(&self, package: &str, info: &Package) -> std::io::Result<()> {
+        let mut options = OpenOptions::new();
+        options.create(true).truncate(false).write(true);
+        // The directory is only created along with the first package of the registry
+        let mut file = match options.open(self.path(package)) {
+            Err(error) if error.kind() == ErrorKind::NotFound => {
+                fs::create_dir_all(&self.dir)?;
+                options.open(self.path(package))?
+            }
+            file => file?,
+        };
+        // Truncate only once we hold the lock, a reader could be halfway through the file
+        file.lock()?;
+        file.set_len(0)?;
+        file.seek(SeekFrom::Start(0))?;
+        file.write_all(serde_json::to_string(info)?.as_bytes())
+    }
+}
@@ -0,0 +1,50 @@
+use crate::consts;
+use failure::Fail;
+use std::fmt;
+
+// Why a registry lookup failed, each kind is shown with its own highlight
+#[derive(Debug)]
+pub enum StoreError {
+    NotFound,
+    // The registry refused our credentials, or their absence
+    Unauthorized,
+    Network(String),
+    RateLimited,
+    Parse(String),
+}
+
+impl StoreError {
+    pub fn highlight(&self) -> &'static str {
+        match self {
+            StoreError::NotFound => consts::NOT_FOUND_HG,
+            StoreError::Unauthorized => consts::ERROR_HG,
+            StoreError::Network(_) => consts::ERROR_HG,
+            StoreError::RateLimited => consts::RATE_LIMITED_HG,
+            StoreError::Parse(_) => consts::PARSE_ERROR_HG,
+        }
+    }
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StoreError::NotFound => write!(f, "not found"),
+            StoreError::Unauthorized => write!(f, "unauthorized, check the registry token"),
+            StoreError::Network(error) => write!(f, "network error: {}", error),
+            StoreError::RateLimited => write!(f, "rate limited"),
+            StoreError::Parse(error) => write!(f, "can't parse response: {}", error),
+        }
+    }
+}
+
+impl Fail for StoreError {}
+
+impl From<reqwest::Error> for StoreError {
+    fn from(error: reqwest::Error) -> Self {
+        if error.is_serialization() {
+            StoreError::Parse(error.to_string())
+        } else {
+            StoreError::Network(error.to_string())
+        }
+    }
+}
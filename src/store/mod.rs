@@ -1,92 +1,617 @@
+mod client;
 mod cratesio;
+mod disk_cache;
+mod error;
 mod npm;
 mod pypi;
 
 use crate::consts;
-use crate::neovim::DependencyInfo;
-use failure::Error;
-use reqwest;
-use semver;
-use serde_json;
+use crate::neovim::{Config, DependencyInfo};
+use disk_cache::DiskCache;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use reqwest::header::{
+    ACCEPT, AUTHORIZATION, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, RETRY_AFTER,
+};
+use reqwest::{RequestBuilder, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
 
-pub use cratesio::Cratesio;
+pub use cratesio::{cargo_home, Cratesio};
+pub use disk_cache::now;
+pub use error::StoreError;
 pub use npm::Npm;
 pub use pypi::Pypi;
 
-pub trait Store {
-    // A method to retrieve package info given base_url and package name
-    // Should be the same for all stores, so we give a default implementation here
-    fn get_package_info(package: &str) -> Result<serde_json::Value, Error> {
-        let url: String = Self::get_url().replace("{package}", package);
-        Ok(reqwest::get(&url)?.json()?)
+#[derive(Clone, Copy, PartialEq)]
+pub enum UpdateKind {
+    Major,
+    Minor,
+    Patch,
+}
+
+/* Unix timestamp of an ISO 8601 date like 2019-05-28T18:19:17.231Z, as registries give them.
+ * Dates without an offset are UTC
+ */
+pub fn timestamp(date: &str) -> Option<u64> {
+    let number = |range: std::ops::Range<usize>| -> Option<i64> { date.get(range)?.parse().ok() };
+    let (year, month, day) = (number(0..4)?, number(5..7)?, number(8..10)?);
+    let (hour, minute, second) = match date.get(10..11) {
+        Some("T") | Some(" ") => (number(11..13)?, number(14..16)?, number(17..19)?),
+        _ => (0, 0, 0),
+    };
+    // Days since the epoch of the civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let (y, m) = if month <= 2 {
+        (year - 1, month + 9)
+    } else {
+        (year, month - 3)
+    };
+    let era = y.div_euclid(400);
+    let year_of_era = y - era * 400;
+    let day_of_year = (153 * m + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    let rest = date.get(19..).unwrap_or("");
+    let rest = rest.trim_start_matches(|c: char| c == '.' || c.is_ascii_digit());
+    let offset = match (rest.get(0..1), rest.get(1..3), rest.get(4..6)) {
+        (Some(sign), Some(hours), Some(minutes)) if sign == "+" || sign == "-" => {
+            let offset = hours.parse::<i64>().ok()? * 3600 + minutes.parse::<i64>().ok()? * 60;
+            if sign == "+" {
+                offset
+            } else {
+                -offset
+            }
+        }
+        _ => 0,
+    };
+    let timestamp = days * 86_400 + hour * 3600 + minute * 60 + second - offset;
+    u64::try_from(timestamp).ok()
+}
+
+// The UTC date of a unix timestamp, as YYYY-MM-DD
+pub fn date(timestamp: u64) -> String {
+    // The inverse of the days since the epoch computed above
+    let days = (timestamp / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let m = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * m + 2) / 5 + 1;
+    let month = if m < 10 { m + 3 } else { m - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+// A newer version available in the store
+#[derive(Clone)]
+pub struct Update {
+    pub kind: UpdateKind,
+    pub version: String,
+    // Newest version the toolchain of the manifest can use, when it's not the latest
+    pub compatible: Option<String>,
+    pub behind: Behind,
+}
+
+// Stable releases newer than the current version, by kind of update
+#[derive(Clone, Default)]
+pub struct Behind {
+    pub major: usize,
+    pub minor: usize,
+    pub patch: usize,
+    // Unix timestamp of the first of them, when release dates are known
+    pub since: Option<u64>,
+}
+
+impl Behind {
+    pub fn total(&self) -> usize {
+        self.major + self.minor + self.patch
+    }
+}
+
+/* Leading numbers of a version, like [1, 2] for 1.2.post1 or [2024, 1] for 2024.1,
+ * to tell the kind of an update whatever the versioning scheme of the store
+ */
+fn release_numbers(version: &str) -> Vec<u64> {
+    version
+        .trim_start_matches('v')
+        .split('.')
+        .map_while(|part| {
+            let digits: String = part.chars().take_while(|c| c.is_ascii_digit()).collect();
+            digits.parse().ok()
+        })
+        .collect()
+}
+
+// The kind of update going from current to version, missing numbers count as zeros
+fn update_kind(current: &str, version: &str) -> UpdateKind {
+    let (current, version) = (release_numbers(current), release_numbers(version));
+    let number = |numbers: &[u64], index: usize| numbers.get(index).copied().unwrap_or(0);
+    if number(&version, 0) != number(&current, 0) {
+        UpdateKind::Major
+    } else if number(&version, 1) != number(&current, 1) {
+        UpdateKind::Minor
+    } else {
+        UpdateKind::Patch
+    }
+}
+
+// Count the stable releases newer than the current version
+pub fn releases_behind(store: &dyn Store, package: &Package, current: &str) -> Behind {
+    let mut behind = Behind::default();
+    for version in &package.versions {
+        if store.is_prerelease(version)
+            || store.compare_versions(version, current) != Some(Ordering::Greater)
+        {
+            continue;
+        }
+        match update_kind(current, version) {
+            UpdateKind::Major => behind.major += 1,
+            UpdateKind::Minor => behind.minor += 1,
+            UpdateKind::Patch => behind.patch += 1,
+        }
+        if let Some(released) = package.released.get(version) {
+            behind.since = Some(behind.since.map_or(*released, |since| since.min(*released)));
+        }
     }
+    behind
+}
 
-    // A method to retrieve the last version of a package given its name in the store
-    fn get_max_version(package: &str) -> Result<String, Error>;
+// What we keep of a registry lookup
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Package {
+    pub max_version: String,
+    pub versions: Vec<String>,
+    // Unix timestamp of the lookup
+    pub fetched_at: u64,
+    // Validators sent back to the registry to check if the package changed
+    #[serde(default)]
+    pub etag: Option<String>,
+    #[serde(default)]
+    pub last_modified: Option<String>,
+    // SPDX license expression of each version, when the registry gives it
+    #[serde(default)]
+    pub licenses: HashMap<String, String>,
+    // Toolchain required by each version, like the rust_version of a crate
+    #[serde(default)]
+    pub toolchains: HashMap<String, String>,
+    // Unix timestamp of the release of each version
+    #[serde(default)]
+    pub released: HashMap<String, u64>,
+    // Unix timestamp of the latest release, of any version
+    #[serde(default)]
+    pub last_release: Option<u64>,
+    // Url of the source repository
+    #[serde(default)]
+    pub repository: Option<String>,
+}
 
-    /* This should return the full url containing the keyword "{package}"
-     * where the package name should be placed in the url (see get_package_info)
+impl Package {
+    pub fn license(&self, version: &str) -> Option<&str> {
+        self.licenses.get(version).map(|license| license.as_str())
+    }
+}
+
+// A registry response, along with its validators
+pub struct PackageInfo {
+    pub body: serde_json::Value,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+// The text to display for a dependency, along with the update found if any
+pub type Check = (Vec<(String, String)>, Option<Update>);
+
+/* Use the Retry-After header of the response, when given in seconds.
+ * The HTTP date form is ignored, the usual backoff applies then
+ */
+fn retry_after(response: &reqwest::Response) -> Option<Duration> {
+    let retry_after = response.headers().get(RETRY_AFTER)?.to_str().ok()?;
+    Some(Duration::from_secs(retry_after.trim().parse().ok()?))
+}
+
+// One pool per registry, shared by every lookup made to it
+static POOLS: OnceLock<Mutex<HashMap<String, Arc<ThreadPool>>>> = OnceLock::new();
+
+// Where a store sends its requests, and the credentials to send along
+#[derive(Clone)]
+pub struct Registry {
+    // Url the package name is appended to
+    pub url: String,
+    pub token: Option<String>,
+}
+
+impl Registry {
+    pub fn new(url: &str) -> Self {
+        Registry {
+            url: url.trim_end_matches('/').to_string(),
+            token: None,
+        }
+    }
+
+    // The registry configured for the ecosystem, or the given default one
+    pub fn configured(ecosystem: &str, url: &str, config: &Config) -> Self {
+        match config.registries.get(ecosystem) {
+            Some(registry) => registry.clone(),
+            None => Registry::new(url),
+        }
+    }
+}
+
+// The store of an ecosystem, pointed at the registry configured for it
+pub fn get_store(ecosystem: &str, config: &Config) -> Option<Arc<dyn Store>> {
+    let store: Arc<dyn Store> = match ecosystem {
+        "cargo" => Arc::new(Cratesio::from_config(config)),
+        "npm" => Arc::new(Npm::from_config(config)),
+        "pypi" => Arc::new(Pypi::from_config(config)),
+        _ => return None,
+    };
+    Some(store)
+}
+
+pub trait Store: Send + Sync {
+    /* A method to retrieve package info given base_url and package name
+     * Should be the same for all stores, so we give a default implementation here.
+     * When a cached package is given the request is conditional, and None is returned
+     * if the registry says the package didn't change since.
+     * Timeouts, rate limits and server errors are retried with an exponential backoff.
      */
-    fn get_url() -> String;
-
-    // Check dependency and return a string
-    fn check_dependency(dep: &DependencyInfo) -> Vec<(String, String)> {
-        // Get store version first
-        let store_version = match Self::get_max_version(&dep.name) {
-            Ok(store_version) => store_version,
-            Err(_) => {
-                return vec![(
-                    format!(" -> Error retrieving version for {}", dep.name),
-                    consts::GREY_HG.to_string(),
-                )]
+    fn get_package_info(
+        &self,
+        package: &str,
+        cached: Option<&Package>,
+        config: &Config,
+    ) -> Result<Option<PackageInfo>, StoreError> {
+        let url = self.get_url(package);
+        let client = client::get_client(config)?;
+        let mut attempt = 0;
+        loop {
+            let mut request = client.get(&url).header(ACCEPT, self.get_accept().as_str());
+            if let Some(token) = &self.registry().token {
+                request = self.authorize(request, token);
             }
-        };
+            if let Some(cached) = cached {
+                if let Some(etag) = &cached.etag {
+                    request = request.header(IF_NONE_MATCH, etag.as_str());
+                }
+                if let Some(last_modified) = &cached.last_modified {
+                    request = request.header(IF_MODIFIED_SINCE, last_modified.as_str());
+                }
+            }
+            let (error, wait) = match request.send() {
+                Ok(mut response) => {
+                    let status = response.status();
+                    if status == StatusCode::NOT_MODIFIED {
+                        return Ok(None);
+                    } else if status == StatusCode::NOT_FOUND {
+                        return Err(StoreError::NotFound);
+                    } else if status == StatusCode::TOO_MANY_REQUESTS {
+                        (StoreError::RateLimited, retry_after(&response))
+                    } else if status.is_server_error() {
+                        let error = StoreError::Network(status.to_string());
+                        (error, retry_after(&response))
+                    } else if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN
+                    {
+                        return Err(StoreError::Unauthorized);
+                    } else if !status.is_success() {
+                        // Retrying won't help, and the error body is no package to cache
+                        return Err(StoreError::Network(status.to_string()));
+                    } else {
+                        let headers = response.headers();
+                        let etag = headers.get(ETAG).and_then(|etag| etag.to_str().ok());
+                        let last_modified = headers
+                            .get(LAST_MODIFIED)
+                            .and_then(|last_modified| last_modified.to_str().ok());
+                        return Ok(Some(PackageInfo {
+                            etag: etag.map(|etag| etag.to_string()),
+                            last_modified: last_modified
+                                .map(|last_modified| last_modified.to_string()),
+                            body: response.json()?,
+                        }));
+                    }
+                }
+                Err(error) => (StoreError::from(error), None),
+            };
+            if attempt >= config.retries {
+                return Err(error);
+            }
+            // Double the wait at every attempt, unless the registry told us how long to wait
+            let backoff = consts::RETRY_BACKOFF * 2u32.saturating_pow(attempt as u32);
+            thread::sleep(wait.unwrap_or(backoff).min(consts::MAX_RETRY_WAIT));
+            attempt += 1;
+        }
+    }
 
-        // Current from lockfile
-        let current = match semver::Version::parse(&dep.current) {
-            Ok(current) => current,
-            Err(_) => return vec![(format!(" {}", store_version), consts::GREY_HG.to_string())],
+    // Look the package up in the on disk cache first, then in the registry
+    fn get_package(&self, package: &str, config: &Config) -> Result<Package, StoreError> {
+        let cache = DiskCache::new(&self.get_cache_key(), config.cache_ttl);
+        let cached = cache.as_ref().and_then(|cache| cache.get(package));
+        if let (Some(cache), Some(cached)) = (&cache, &cached) {
+            if cache.is_fresh(cached) {
+                return Ok(cached.clone());
+            }
+        }
+        let info = match self.get_package_info(package, cached.as_ref(), config)? {
+            Some(info) => Package {
+                max_version: self.parse_max_version(&info.body),
+                versions: self.parse_versions(&info.body),
+                licenses: self.parse_licenses(&info.body),
+                toolchains: self.parse_toolchains(&info.body),
+                released: self.parse_released(&info.body),
+                last_release: self.parse_last_release(&info.body),
+                repository: self.parse_repository(&info.body),
+                fetched_at: disk_cache::now(),
+                etag: info.etag,
+                last_modified: info.last_modified,
+            },
+            // Not modified, only remember we checked. We only send validators
+            // when we have a cached package, so it must be there
+            None => Package {
+                fetched_at: disk_cache::now(),
+                ..cached.ok_or(StoreError::NotFound)?
+            },
         };
+        if let Some(cache) = cache {
+            cache.set(package, &info);
+        }
+        Ok(info)
+    }
 
-        // Latest store version
-        let latest_version = match semver::Version::parse(&store_version) {
-            Ok(latest_version) => latest_version,
-            Err(_) => return vec![(format!(" {}", store_version), consts::GREY_HG.to_string())],
+    // Download the archive of a version, with the registry credentials
+    fn get_tarball(
+        &self,
+        package: &str,
+        version: &str,
+        config: &Config,
+    ) -> Result<Option<Vec<u8>>, StoreError> {
+        let url = match self.get_tarball_url(package, version) {
+            Some(url) => url,
+            None => return Ok(None),
         };
+        let mut request = client::get_client(config)?.get(&url);
+        if let Some(token) = &self.registry().token {
+            request = self.authorize(request, token);
+        }
+        let mut response = request.send()?;
+        let status = response.status();
+        if status == StatusCode::NOT_FOUND {
+            return Err(StoreError::NotFound);
+        } else if !status.is_success() {
+            return Err(StoreError::Network(status.to_string()));
+        }
+        let mut tarball = vec![];
+        response.copy_to(&mut tarball)?;
+        Ok(Some(tarball))
+    }
+
+    // A method to retrieve all the published versions of a package, used to compute upgrades
+    fn get_versions(&self, package: &str, config: &Config) -> Result<Vec<String>, StoreError> {
+        Ok(self.get_package(package, config)?.versions)
+    }
+
+    // Extract the last version from the package info returned by the store
+    fn parse_max_version(&self, body: &serde_json::Value) -> String;
+
+    // Extract all the published versions from the package info returned by the store
+    fn parse_versions(&self, body: &serde_json::Value) -> Vec<String>;
+
+    // Extract the license of each version from the package info returned by the store
+    fn parse_licenses(&self, body: &serde_json::Value) -> HashMap<String, String>;
+
+    // License of a version, stores can look it up when the package info doesn't have it
+    fn get_license(
+        &self,
+        package: &Package,
+        _name: &str,
+        version: &str,
+        _config: &Config,
+    ) -> Option<String> {
+        package.license(version).map(|license| license.to_string())
+    }
+
+    // Extract the release timestamp of each version from the package info returned by the store
+    fn parse_released(&self, body: &serde_json::Value) -> HashMap<String, u64>;
+
+    // When the package last released, the newest of its versions by default
+    fn parse_last_release(&self, body: &serde_json::Value) -> Option<u64> {
+        self.parse_released(body).values().max().copied()
+    }
+
+    // Extract the url of the source repository, if the registry gives it
+    fn parse_repository(&self, _body: &serde_json::Value) -> Option<String> {
+        None
+    }
+
+    // Url of the archive of a version, for registries serving them as gzipped tarballs
+    fn get_tarball_url(&self, _package: &str, _version: &str) -> Option<String> {
+        None
+    }
+
+    // Extract the toolchain required by each version, for registries giving it
+    fn parse_toolchains(&self, _body: &serde_json::Value) -> HashMap<String, String> {
+        HashMap::new()
+    }
+
+    // Name of the toolchain versions are built with, as shown next to its version
+    fn toolchain(&self) -> &'static str;
+
+    // Whether a version requiring the given toolchain can be used with the manifest's one
+    fn is_compatible(&self, _required: &str, _toolchain: &str) -> bool {
+        true
+    }
+
+    // Order of two versions of the registry, None when either can't be parsed
+    fn compare_versions(&self, a: &str, b: &str) -> Option<Ordering> {
+        let a = semver::Version::parse(a).ok()?;
+        Some(a.cmp(&semver::Version::parse(b).ok()?))
+    }
+
+    // Versions that can't be parsed are not stable ones either
+    fn is_prerelease(&self, version: &str) -> bool {
+        semver::Version::parse(version).map_or(true, |version| version.is_prerelease())
+    }
+
+    // Whether the manifest requirement allows the locked version, None when either can't be parsed
+    fn satisfies(&self, requirement: &str, version: &str) -> Option<bool> {
+        let requirement = semver::VersionReq::parse(requirement).ok()?;
+        Some(requirement.matches(&semver::Version::parse(version).ok()?))
+    }
+
+    // The registry the store was configured with
+    fn registry(&self) -> &Registry;
+
+    // One of "cargo", "npm" and "pypi", as in the configuration
+    fn ecosystem(&self) -> &'static str;
+
+    // Full url of the package info, most registries append the name to their url
+    fn get_url(&self, package: &str) -> String {
+        format!("{}/{}", self.registry().url, package)
+    }
+
+    // Send the registry token, stores can pick another authentication scheme
+    fn authorize(&self, request: RequestBuilder, token: &str) -> RequestBuilder {
+        request.header(AUTHORIZATION, token)
+    }
 
-        if latest_version.major != current.major {
-            vec![(
-                format!(" -> {}", latest_version),
-                consts::RED_HG.to_string(),
-            )]
-        } else if latest_version.minor != current.minor {
-            let split: Vec<String> = latest_version
-                .to_string()
-                .split('.')
-                .map(|x| x.to_string())
-                .collect();
-            vec![
-                (
-                    format!(" -> {}.", split[0]).to_string(),
-                    consts::GREY_HG.to_string(),
-                ),
-                (split[1..].join("."), consts::BLUE_HG.to_string()),
-            ]
-        } else if latest_version.patch != current.patch {
-            let split: Vec<String> = latest_version
-                .to_string()
-                .split('.')
-                .map(|x| x.to_string())
-                .collect();
-            vec![
-                (
-                    format!(" -> {}.", split[..2].join(".")).to_string(),
-                    consts::GREY_HG.to_string(),
-                ),
-                (split[2..].join("."), "String".to_string()),
-            ]
-        } else {
-            vec![]
+    /* Keeps the packages of the registry apart in the memory and disk caches.
+     * Stores fetching different documents from the same registry must use different keys
+     */
+    fn get_cache_key(&self) -> String {
+        self.registry().url.clone()
+    }
+
+    /* Host of the registry, the registries it serves share a thread pool.
+     * The port is kept, so a registry running locally gets its own pool
+     */
+    fn get_registry(&self) -> String {
+        let url = &self.registry().url;
+        let host = url.split("://").last().unwrap_or(url);
+        host.split('/').next().unwrap_or(host).to_string()
+    }
+
+    // Accept header of the registry requests, stores can ask for a lighter document
+    fn get_accept(&self) -> String {
+        "application/json".to_string()
+    }
+
+    // Maximum number of concurrent requests to the registry
+    fn get_concurrency(&self) -> usize {
+        consts::MAX_CONCURRENT_REQUESTS
+    }
+
+    /* Thread pool to run the registry lookups in, so a big manifest doesn't
+     * flood the registry with requests (and doesn't take over rayon's global pool)
+     */
+    fn get_pool(&self) -> Arc<ThreadPool> {
+        let pools = POOLS.get_or_init(|| Mutex::new(HashMap::new()));
+        let mut pools = pools.lock().unwrap();
+        pools
+            .entry(self.get_registry())
+            .or_insert_with(|| {
+                let pool = ThreadPoolBuilder::new()
+                    .num_threads(self.get_concurrency())
+                    .build()
+                    .expect("Error building the registry thread pool");
+                Arc::new(pool)
+            })
+            .clone()
+    }
+}
+
+// Check dependency against the latest version in the store
+pub fn check_dependency(
+    store: &dyn Store,
+    dep: &DependencyInfo,
+    package: Result<&Package, &StoreError>,
+    config: &Config,
+) -> Check {
+    // Get store version first
+    let package = match package {
+        Ok(package) => package,
+        Err(error) => {
+            return (
+                vec![(
+                    format!("{}{} {}", config.separator, dep.name, error),
+                    error.highlight().to_string(),
+                )],
+                None,
+            )
         }
+    };
+    let latest_version = &package.max_version;
+
+    // Compare with the current version from the lockfile, as the store orders its versions
+    match store.compare_versions(latest_version, &dep.current) {
+        // A pre-release can be ahead of the latest version the registry advertises
+        Some(Ordering::Greater) => (),
+        Some(_) => return (vec![], None),
+        None => {
+            return (
+                vec![(
+                    format!(" {}", latest_version),
+                    consts::UP_TO_DATE_HG.to_string(),
+                )],
+                None,
+            )
+        }
+    }
+    // Versions differing only by their pre-release are shown as a patch
+    let kind = update_kind(&dep.current, latest_version);
+
+    let split: Vec<&str> = latest_version.split('.').collect();
+    // Numbers kept in the up to date highlight, at least the last part is highlighted
+    let kept = match kind {
+        UpdateKind::Major => 0,
+        UpdateKind::Minor => 1,
+        UpdateKind::Patch => 2,
     }
+    .min(split.len() - 1);
+    let chunks = match kept {
+        0 => vec![(
+            format!("{}{}", config.separator, latest_version),
+            consts::MAJOR_HG.to_string(),
+        )],
+        kept => vec![
+            (
+                format!("{}{}.", config.separator, split[..kept].join(".")),
+                consts::UP_TO_DATE_HG.to_string(),
+            ),
+            (
+                split[kept..].join("."),
+                match kind {
+                    UpdateKind::Minor => consts::MINOR_HG.to_string(),
+                    _ => consts::PATCH_HG.to_string(),
+                },
+            ),
+        ],
+    };
+    let update = Update {
+        kind,
+        version: latest_version.to_string(),
+        compatible: None,
+        behind: releases_behind(store, package, &dep.current),
+    };
+    (chunks, Some(update))
+}
+
+// The newest stable version usable with the toolchain, versions without requirements always are
+pub fn newest_compatible(store: &dyn Store, package: &Package, toolchain: &str) -> Option<String> {
+    package
+        .versions
+        .iter()
+        .filter(|version| {
+            package
+                .toolchains
+                .get(*version)
+                .is_none_or(|required| store.is_compatible(required, toolchain))
+        })
+        .filter(|version| !store.is_prerelease(version))
+        .max_by(|a, b| store.compare_versions(a, b).unwrap_or(Ordering::Equal))
+        .map(|version| version.to_string())
 }
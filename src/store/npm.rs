@@ -1,20 +1,248 @@
-use crate::store::Store;
-use failure::Error;
+use crate::neovim::Config;
+use crate::store::{timestamp, Registry, Store};
 
-pub struct Npm;
+use reqwest::RequestBuilder;
+use std::collections::HashMap;
+
+// A partial version like 14 or 14.x, with the missing numbers as zeros
+fn partial_version(version: &str) -> Option<semver::Version> {
+    let mut parts: Vec<&str> = version
+        .trim_start_matches('v')
+        .split('.')
+        .map(|part| match part {
+            "x" | "X" | "*" => "0",
+            part => part,
+        })
+        .collect();
+    while parts.len() < 3 {
+        parts.push("0");
+    }
+    semver::Version::parse(&parts.join(".")).ok()
+}
+
+/* The comparators of an npm range, in the comma separated form semver understands:
+ * operators can be followed by a space, and a - b is an inclusive range
+ */
+fn comparators(range: &str) -> Vec<String> {
+    let tokens: Vec<&str> = range.split_whitespace().collect();
+    let mut comparators: Vec<String> = vec![];
+    let mut index = 0;
+    while index < tokens.len() {
+        let token = tokens[index];
+        if tokens.get(index + 1) == Some(&"-") && index + 2 < tokens.len() {
+            comparators.push(format!(">={}", token));
+            comparators.push(format!("<={}", tokens[index + 2]));
+            index += 3;
+        } else if token.chars().all(|c| "<>=^~".contains(c)) && index + 1 < tokens.len() {
+            comparators.push(format!("{}{}", token, tokens[index + 1]));
+            index += 2;
+        } else {
+            comparators.push(token.to_string());
+            index += 1;
+        }
+    }
+    comparators
+}
+
+// Whether the version is in the range, None if the range can't be parsed
+fn range_matches(range: &str, version: &semver::Version) -> Option<bool> {
+    for alternative in range.split("||") {
+        let comparators = comparators(alternative);
+        let requirement = match comparators.is_empty() {
+            true => semver::VersionReq::any(),
+            false => semver::VersionReq::parse(&comparators.join(", ")).ok()?,
+        };
+        if requirement.matches(version) {
+            return Some(true);
+        }
+    }
+    Some(false)
+}
+
+// The oldest version allowed by a range, None if it has no lower bound
+fn lowest_version(range: &str) -> Option<semver::Version> {
+    range
+        .split("||")
+        .flat_map(comparators)
+        .filter(|comparator| !comparator.starts_with('<'))
+        .filter_map(|comparator| {
+            partial_version(comparator.trim_start_matches(['>', '=', '^', '~']))
+        })
+        .min()
+}
+
+pub struct Npm {
+    registry: Registry,
+    // Whether the abbreviated metadata is enough, it has no licenses nor release dates
+    abbreviated: bool,
+}
+
+impl Npm {
+    pub const URL: &'static str = "https://registry.npmjs.org";
+
+    pub fn new(registry: Registry) -> Self {
+        Npm {
+            registry,
+            abbreviated: true,
+        }
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        Npm {
+            abbreviated: !config.version_details(),
+            ..Npm::new(Registry::configured("npm", Self::URL, config))
+        }
+    }
+
+    // Older packages have a {type, url} object, or a list of them to choose from
+    fn license(version: &serde_json::Value) -> Option<String> {
+        match &version["license"] {
+            serde_json::Value::String(license) => Some(license.to_string()),
+            serde_json::Value::Object(license) => Some(license.get("type")?.as_str()?.to_string()),
+            _ => {
+                let licenses: Vec<&str> = version["licenses"]
+                    .as_array()?
+                    .iter()
+                    .filter_map(|license| license["type"].as_str())
+                    .collect();
+                match licenses.len() {
+                    0 => None,
+                    1 => Some(licenses[0].to_string()),
+                    _ => Some(format!("({})", licenses.join(" OR "))),
+                }
+            }
+        }
+    }
+}
 
 impl Store for Npm {
-    fn get_url() -> String {
-        "https://registry.npmjs.org/{package}".to_string()
+    fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    fn ecosystem(&self) -> &'static str {
+        "npm"
     }
 
-    fn get_max_version(package: &str) -> Result<String, Error> {
-        let body = Self::get_package_info(package)?;
+    // Same token as the _authToken of an .npmrc
+    fn authorize(&self, request: RequestBuilder, token: &str) -> RequestBuilder {
+        request.bearer_auth(token)
+    }
 
+    // An abbreviated document can't stand in for a full one, a fragment keeps them apart
+    fn get_cache_key(&self) -> String {
+        if self.abbreviated {
+            self.registry.url.clone()
+        } else {
+            format!("{}#full", self.registry.url)
+        }
+    }
+
+    // Abbreviated metadata only has what is needed to install a package,
+    // which is way smaller than the full document for popular packages
+    fn get_accept(&self) -> String {
+        if self.abbreviated {
+            "application/vnd.npm.install-v1+json; q=1.0, application/json; q=0.8".to_string()
+        } else {
+            "application/json".to_string()
+        }
+    }
+
+    fn parse_max_version(&self, body: &serde_json::Value) -> String {
         if let Some(res) = body["dist-tags"]["latest"].as_str() {
-            Ok(res.to_string())
+            res.to_string()
         } else {
-            Ok("Can't find version".to_string())
+            "Can't find version".to_string()
+        }
+    }
+
+    fn parse_versions(&self, body: &serde_json::Value) -> Vec<String> {
+        match body["versions"].as_object() {
+            Some(versions) => versions.keys().cloned().collect(),
+            None => vec![],
+        }
+    }
+
+    // Only in the full metadata, along with the created and modified dates of the package
+    fn parse_released(&self, body: &serde_json::Value) -> HashMap<String, u64> {
+        match body["time"].as_object() {
+            Some(time) => time
+                .iter()
+                .filter(|(version, _)| *version != "created" && *version != "modified")
+                .filter_map(|(version, date)| Some((version.clone(), timestamp(date.as_str()?)?)))
+                .collect(),
+            None => HashMap::new(),
+        }
+    }
+
+    // The abbreviated metadata only says when the package was last modified, usually by a release
+    fn parse_last_release(&self, body: &serde_json::Value) -> Option<u64> {
+        let released = self.parse_released(body).values().max().copied();
+        released.or_else(|| timestamp(body["modified"].as_str()?))
+    }
+
+    fn parse_toolchains(&self, body: &serde_json::Value) -> HashMap<String, String> {
+        match body["versions"].as_object() {
+            Some(versions) => versions
+                .iter()
+                .filter_map(|(version, info)| {
+                    Some((
+                        version.clone(),
+                        info["engines"]["node"].as_str()?.to_string(),
+                    ))
+                })
+                .collect(),
+            None => HashMap::new(),
+        }
+    }
+
+    fn toolchain(&self) -> &'static str {
+        "node"
+    }
+
+    // Only in the full metadata, often as a git url
+    fn parse_repository(&self, body: &serde_json::Value) -> Option<String> {
+        let repository = &body["repository"];
+        let url = repository.as_str().or_else(|| repository["url"].as_str())?;
+        let url = url.trim_start_matches("git+").trim_end_matches(".git");
+        Some(match url.split_once(':') {
+            Some(("github", path)) => format!("https://github.com/{}", path),
+            Some(("git", path)) => format!("https:{}", path),
+            Some(("git@github.com", path)) => format!("https://github.com/{}", path),
+            // Shorthand for a GitHub repository
+            None if url.contains('/') => format!("https://github.com/{}", url),
+            _ => url.to_string(),
+        })
+    }
+
+    // Scoped packages keep the scope out of the archive name
+    fn get_tarball_url(&self, package: &str, version: &str) -> Option<String> {
+        let name = package.rsplit('/').next().unwrap_or(package);
+        Some(format!(
+            "{}/{}/-/{}-{}.tgz",
+            self.registry.url, package, name, version
+        ))
+    }
+
+    fn satisfies(&self, requirement: &str, version: &str) -> Option<bool> {
+        range_matches(requirement, &semver::Version::parse(version).ok()?)
+    }
+
+    // The manifest's engines are a range too, the oldest node it allows must be supported
+    fn is_compatible(&self, required: &str, toolchain: &str) -> bool {
+        match lowest_version(toolchain) {
+            Some(lowest) => range_matches(required, &lowest).unwrap_or(true),
+            None => true,
+        }
+    }
+
+    fn parse_licenses(&self, body: &serde_json::Value) -> HashMap<String, String> {
+        match body["versions"].as_object() {
+            Some(versions) => versions
+                .iter()
+                .filter_map(|(version, info)| Some((version.clone(), Self::license(info)?)))
+                .collect(),
+            None => HashMap::new(),
         }
     }
 }
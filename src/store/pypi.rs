@@ -1,19 +1,229 @@
-use crate::store::Store;
-use failure::Error;
+use crate::neovim::Config;
+use crate::python::Version;
+use crate::store::{timestamp, Package, Registry, Store};
 
-pub struct Pypi;
+use reqwest::RequestBuilder;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+// SPDX ids of the most common license classifiers
+static CLASSIFIERS: [(&str, &str); 11] = [
+    ("Apache Software License", "Apache-2.0"),
+    ("GNU Affero General Public License v3", "AGPL-3.0-only"),
+    ("GNU General Public License v2 (GPLv2)", "GPL-2.0-only"),
+    ("GNU General Public License v3 (GPLv3)", "GPL-3.0-only"),
+    (
+        "GNU Lesser General Public License v2 (LGPLv2)",
+        "LGPL-2.0-only",
+    ),
+    (
+        "GNU Lesser General Public License v3 (LGPLv3)",
+        "LGPL-3.0-only",
+    ),
+    ("ISC License (ISCL)", "ISC"),
+    ("MIT License", "MIT"),
+    ("Mozilla Public License 2.0 (MPL 2.0)", "MPL-2.0"),
+    ("Python Software Foundation License", "PSF-2.0"),
+    ("The Unlicense (Unlicense)", "Unlicense"),
+];
+
+pub struct Pypi {
+    registry: Registry,
+}
+
+impl Pypi {
+    pub const URL: &'static str = "https://pypi.org/pypi";
+
+    pub fn new(registry: Registry) -> Self {
+        Pypi { registry }
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        Pypi::new(Registry::configured("pypi", Self::URL, config))
+    }
+
+    /* The license field is free text, and sometimes the whole license, so the
+     * SPDX expression of newer packages comes first, then the classifiers
+     */
+    fn license(info: &serde_json::Value) -> Option<String> {
+        if let Some(expression) = info["license_expression"].as_str() {
+            return Some(expression.to_string());
+        }
+        let classifiers: Vec<&str> = info["classifiers"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|classifier| classifier.as_str()?.strip_prefix("License :: "))
+            .map(|classifier| classifier.rsplit(" :: ").next().unwrap_or(classifier))
+            .collect();
+        let known: Vec<&str> = classifiers
+            .iter()
+            .filter_map(|classifier| {
+                let (_, id) = CLASSIFIERS.iter().find(|(name, _)| name == classifier)?;
+                Some(*id)
+            })
+            .collect();
+        if !known.is_empty() {
+            return Some(known.join(" OR "));
+        }
+        match info["license"].as_str().map(|license| license.trim()) {
+            Some(license)
+                if !license.is_empty() && !license.contains('\n') && license.len() <= 64 =>
+            {
+                Some(license.to_string())
+            }
+            _ => classifiers.first().map(|classifier| classifier.to_string()),
+        }
+    }
+}
 
 impl Store for Pypi {
-    fn get_url() -> String {
-        "https://pypi.org/pypi/{package}/json".to_string()
+    fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    fn ecosystem(&self) -> &'static str {
+        "pypi"
+    }
+
+    fn get_url(&self, package: &str) -> String {
+        format!("{}/{}/json", self.registry.url, package)
+    }
+
+    // Private indexes usually take a "username:password" basic authentication
+    fn authorize(&self, request: RequestBuilder, token: &str) -> RequestBuilder {
+        match token.split_once(':') {
+            Some((username, password)) => request.basic_auth(username, Some(password)),
+            None => request.bearer_auth(token),
+        }
     }
 
-    fn get_max_version(package: &str) -> Result<String, Error> {
-        let body = Self::get_package_info(package)?;
+    fn parse_max_version(&self, body: &serde_json::Value) -> String {
         if let Some(res) = body["info"]["version"].as_str() {
-            Ok(res.to_string())
+            res.to_string()
         } else {
-            Ok("Can't find version".to_string())
+            "Can't find version".to_string()
+        }
+    }
+
+    fn parse_versions(&self, body: &serde_json::Value) -> Vec<String> {
+        match body["releases"].as_object() {
+            Some(releases) => releases.keys().cloned().collect(),
+            None => vec![],
+        }
+    }
+
+    // A release is out when its first file is uploaded
+    fn parse_released(&self, body: &serde_json::Value) -> HashMap<String, u64> {
+        match body["releases"].as_object() {
+            Some(releases) => releases
+                .iter()
+                .filter_map(|(version, files)| {
+                    let uploaded = files
+                        .as_array()?
+                        .iter()
+                        .filter_map(|file| {
+                            let date = file["upload_time_iso_8601"]
+                                .as_str()
+                                .or_else(|| file["upload_time"].as_str())?;
+                            timestamp(date)
+                        })
+                        .min()?;
+                    Some((version.clone(), uploaded))
+                })
+                .collect(),
+            None => HashMap::new(),
+        }
+    }
+
+    // Every file of a release has its requires_python, they rarely differ
+    fn parse_toolchains(&self, body: &serde_json::Value) -> HashMap<String, String> {
+        match body["releases"].as_object() {
+            Some(releases) => releases
+                .iter()
+                .filter_map(|(version, files)| {
+                    let required = files
+                        .as_array()?
+                        .iter()
+                        .find_map(|file| file["requires_python"].as_str())?;
+                    Some((version.clone(), required.to_string()))
+                })
+                .collect(),
+            None => HashMap::new(),
+        }
+    }
+
+    fn toolchain(&self) -> &'static str {
+        "python"
+    }
+
+    // Project urls have free form names, the home page is often the repository too
+    fn parse_repository(&self, body: &serde_json::Value) -> Option<String> {
+        let info = &body["info"];
+        let project_url = info["project_urls"].as_object().and_then(|urls| {
+            urls.iter()
+                .find(|(name, _)| {
+                    let name = name.to_lowercase();
+                    ["source", "source code", "repository", "code", "github"]
+                        .contains(&name.as_str())
+                })
+                .and_then(|(_, url)| url.as_str())
+        });
+        let url = project_url.or_else(|| info["home_page"].as_str())?;
+        Some(url.to_string()).filter(|url| !url.is_empty())
+    }
+
+    fn is_compatible(&self, required: &str, toolchain: &str) -> bool {
+        Version::parse(toolchain)
+            .and_then(|toolchain| toolchain.satisfies(required))
+            .unwrap_or(true)
+    }
+
+    // Pipfile requirements are specifiers, or * for any version
+    fn satisfies(&self, requirement: &str, version: &str) -> Option<bool> {
+        match requirement.trim() {
+            "*" => Some(true),
+            requirement => Version::parse(version)?.satisfies(requirement),
+        }
+    }
+
+    fn compare_versions(&self, a: &str, b: &str) -> Option<Ordering> {
+        Some(Version::parse(a)?.cmp(&Version::parse(b)?))
+    }
+
+    fn is_prerelease(&self, version: &str) -> bool {
+        Version::parse(version).is_none_or(|version| version.is_prerelease())
+    }
+
+    /* Older releases have a document of their own, under the version url. It is looked up
+     * like a package named after the version, so it gets cached along with the others
+     */
+    fn get_license(
+        &self,
+        package: &Package,
+        name: &str,
+        version: &str,
+        config: &Config,
+    ) -> Option<String> {
+        if let Some(license) = package.license(version) {
+            return Some(license.to_string());
+        }
+        if version.is_empty() {
+            return None;
+        }
+        let release = self
+            .get_package(&format!("{}/{}", name, version), config)
+            .ok()?;
+        release.license(version).map(|license| license.to_string())
+    }
+
+    // Only the latest release is described, older ones may have been under another license
+    fn parse_licenses(&self, body: &serde_json::Value) -> HashMap<String, String> {
+        match Self::license(&body["info"]) {
+            Some(license) => vec![(self.parse_max_version(body), license)]
+                .into_iter()
+                .collect(),
+            None => HashMap::new(),
         }
     }
 }
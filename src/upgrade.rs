@@ -0,0 +1,126 @@
+use crate::neovim::DependencyInfo;
+use failure::{format_err, Error};
+use semver::Version;
+use std::str::FromStr;
+
+// Operators we know how to carry over to the upgraded requirement
+static OPERATORS: [&str; 7] = ["", "^", "~", "=", "==", ">=", "~="];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Level {
+    Patch,
+    Minor,
+    Major,
+}
+
+impl FromStr for Level {
+    type Err = Error;
+
+    fn from_str(level: &str) -> Result<Self, Error> {
+        match level {
+            "patch" => Ok(Level::Patch),
+            "minor" => Ok(Level::Minor),
+            "major" => Ok(Level::Major),
+            _ => Err(format_err!(
+                "Unknown upgrade level: {}, use one of patch, minor, major",
+                level
+            )),
+        }
+    }
+}
+
+pub struct Upgrade {
+    pub name: String,
+    pub line_number: i64,
+    pub old: String,
+    pub new: String,
+}
+
+impl Upgrade {
+    pub fn new(dep: &DependencyInfo, versions: &[String], level: Level) -> Option<Self> {
+        let old = dep.requirement.trim_matches('"').to_string();
+        let new = upgrade_requirement(&old, &dep.current, versions, level)?;
+        Some(Upgrade {
+            name: dep.name.clone(),
+            line_number: dep.line_number,
+            old,
+            new,
+        })
+    }
+
+    // Replace the quoted requirement in the manifest line, if we can find it there
+    pub fn apply(&self, line: &str) -> Option<String> {
+        let old = format!("\"{}\"", self.old);
+        if line.contains(&old) {
+            Some(line.replacen(&old, &format!("\"{}\"", self.new), 1))
+        } else {
+            None
+        }
+    }
+}
+
+// Parse a version leniently, padding missing minor and patch numbers with zeroes,
+// since not every registry (PyPI above all) sticks to semver
+pub fn parse_version(version: &str) -> Option<Version> {
+    let version = version.trim().trim_start_matches('v');
+    if let Ok(version) = Version::parse(version) {
+        return Some(version);
+    }
+    let mut parts: Vec<&str> = version.split('.').collect();
+    if parts.len() > 3 || parts.iter().any(|part| part.parse::<u64>().is_err()) {
+        return None;
+    }
+    while parts.len() < 3 {
+        parts.push("0");
+    }
+    Version::parse(&parts.join(".")).ok()
+}
+
+/* Whether upgrading from base to version stays within the level. Like semver does
+ * for 0.x versions, the first non zero number is the major one: from 0.2.3 a minor
+ * upgrade stays within 0.2, and from 0.0.3 neither a minor nor a patch one is possible
+ */
+fn allowed(version: &Version, base: &Version, level: Level) -> bool {
+    let same_minor = version.major == base.major && version.minor == base.minor;
+    match level {
+        Level::Patch => {
+            same_minor && (base.major > 0 || base.minor > 0 || version.patch == base.patch)
+        }
+        Level::Minor if base.major > 0 => version.major == base.major,
+        Level::Minor => same_minor && (base.minor > 0 || version.patch == base.patch),
+        Level::Major => true,
+    }
+}
+
+/* Compute the requirement string pointing to the newest version allowed by the level,
+ * keeping the original operator. Returns None if the requirement is not a single
+ * version we know how to rewrite, or if there is nothing to upgrade.
+ */
+pub fn upgrade_requirement(
+    requirement: &str,
+    current: &str,
+    versions: &[String],
+    level: Level,
+) -> Option<String> {
+    let split = requirement.find(|c: char| c.is_ascii_digit())?;
+    let (operator, required) = requirement.split_at(split);
+    if !OPERATORS.contains(&operator.trim()) {
+        return None;
+    }
+    let required = parse_version(required)?;
+
+    // Upgrade from what is installed, unless we have no lockfile to tell us
+    let base = match parse_version(current) {
+        Some(current) if current > required => current,
+        _ => required,
+    };
+
+    let latest = versions
+        .iter()
+        .filter_map(|version| parse_version(version))
+        .filter(|version| !version.is_prerelease() && *version > base)
+        .filter(|version| allowed(version, &base, level))
+        .max()?;
+
+    Some(format!("{}{}", operator, latest))
+}
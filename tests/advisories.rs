@@ -0,0 +1,184 @@
+use neovim_package_info::advisory::{get_advisories, Advisory, Severity};
+use neovim_package_info::neovim::Config;
+
+fn advisories(name: &str, version: &str) -> Vec<Advisory> {
+    let config = Config {
+        advisory_db: Some(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/advisory-db").to_string(),
+        ),
+        ..Config::default()
+    };
+    get_advisories("cargo", name, version, &config)
+}
+
+fn osv_advisories(databases: &[&str], ecosystem: &str, name: &str, version: &str) -> Vec<Advisory> {
+    let config = Config {
+        osv_db: databases
+            .iter()
+            .map(|database| format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), database))
+            .collect(),
+        ..Config::default()
+    };
+    get_advisories(ecosystem, name, version, &config)
+}
+
+fn ids(advisories: &[Advisory]) -> Vec<&str> {
+    advisories
+        .iter()
+        .map(|advisory| advisory.id.as_str())
+        .collect()
+}
+
+#[test]
+fn vulnerable_versions() {
+    let advisories = advisories("smallvec", "0.6.9");
+    // The most severe first
+    assert_eq!(
+        ids(&advisories),
+        vec!["RUSTSEC-2021-0003", "RUSTSEC-2019-0009"]
+    );
+    assert!(advisories[0].severity == Severity::Critical);
+    assert_eq!(advisories[0].aliases, vec!["CVE-2021-25900"]);
+    assert_eq!(
+        advisories[0].patched,
+        vec![">= 0.6.14, < 1.0.0", ">= 1.6.1"]
+    );
+    assert!(advisories[1].severity == Severity::Unknown);
+    assert_eq!(
+        advisories[1].title,
+        "Double-free and use-after-free in SmallVec::grow()"
+    );
+    assert_eq!(advisories[1].date, "2019-06-06");
+}
+
+#[test]
+fn legacy_toml_advisories() {
+    let advisories = advisories("smallvec", "0.6.0");
+    assert_eq!(
+        ids(&advisories),
+        vec!["RUSTSEC-2021-0003", "RUSTSEC-2018-0003"]
+    );
+    assert_eq!(
+        advisories[1].title,
+        "Possible double free during unwinding in SmallVec::insert_many"
+    );
+}
+
+#[test]
+fn patched_and_unaffected_versions() {
+    assert!(advisories("smallvec", "0.6.14").is_empty());
+    assert!(advisories("smallvec", "1.6.1").is_empty());
+    assert!(advisories("smallvec", "0.3.1").is_empty());
+    assert_eq!(
+        ids(&advisories("smallvec", "1.6.0")),
+        vec!["RUSTSEC-2021-0003"]
+    );
+}
+
+#[test]
+fn informational_advisories() {
+    let advisories = advisories("term", "0.5.2");
+    assert_eq!(ids(&advisories), vec!["RUSTSEC-2018-0015"]);
+    assert_eq!(advisories[0].informational.as_deref(), Some("unmaintained"));
+    assert!(advisories[0].patched.is_empty());
+}
+
+#[test]
+fn withdrawn_and_unknown() {
+    assert!(advisories("chrono", "0.4.19").is_empty());
+    assert!(advisories("serde", "1.0.0").is_empty());
+    // Missing lockfiles leave versions we can't match
+    assert!(advisories("smallvec", "not a version").is_empty());
+}
+
+#[test]
+fn osv_npm_ranges() {
+    let advisories = osv_advisories(&["osv/npm"], "npm", "lodash", "4.17.11");
+    assert_eq!(
+        ids(&advisories),
+        vec!["GHSA-jf85-cpcp-j695", "GHSA-p6mc-m468-83gw"]
+    );
+    // Labels given by the database
+    assert!(advisories[0].severity == Severity::Critical);
+    assert!(advisories[1].severity == Severity::High);
+    assert_eq!(advisories[1].title, "Prototype Pollution in lodash");
+    assert_eq!(advisories[1].date, "2020-07-15");
+    assert_eq!(advisories[1].patched, vec![">= 4.17.19"]);
+    assert_eq!(
+        advisories[1].url.as_deref(),
+        Some("https://nvd.nist.gov/vuln/detail/CVE-2020-8203")
+    );
+
+    assert_eq!(
+        ids(&osv_advisories(&["osv/npm"], "npm", "lodash", "4.17.12")),
+        vec!["GHSA-p6mc-m468-83gw"]
+    );
+    // Before the introduced version, and the withdrawn advisory is ignored
+    assert_eq!(
+        ids(&osv_advisories(&["osv/npm"], "npm", "lodash", "3.6.0")),
+        vec!["GHSA-jf85-cpcp-j695"]
+    );
+    assert!(osv_advisories(&["osv/npm"], "npm", "lodash", "4.17.21").is_empty());
+}
+
+#[test]
+fn osv_npm_pre_releases_and_last_affected() {
+    let traverse = |version| osv_advisories(&["osv/npm"], "npm", "@babel/traverse", version);
+    let advisories = traverse("7.23.1");
+    assert_eq!(ids(&advisories), vec!["GHSA-67hx-6x53-jw92"]);
+    // Scored with the CVSS vector when there is no label
+    assert!(advisories[0].severity == Severity::High);
+    assert_eq!(advisories[0].patched, vec![">= 7.23.2", ">= 8.0.0-alpha.4"]);
+    assert!(traverse("7.23.2").is_empty());
+    assert_eq!(traverse("8.0.0-alpha.3").len(), 1);
+    assert!(traverse("8.0.0-alpha.4").is_empty());
+
+    let traverse = |version| osv_advisories(&["osv/npm"], "npm", "babel-traverse", version);
+    assert_eq!(traverse("6.26.0").len(), 1);
+    assert!(traverse("6.26.1").is_empty());
+}
+
+#[test]
+fn osv_zip_database() {
+    // The same advisories found in both databases are listed once
+    let advisories = osv_advisories(&["osv-npm.zip", "osv/npm"], "npm", "lodash", "4.17.11");
+    assert_eq!(
+        ids(&advisories),
+        vec!["GHSA-jf85-cpcp-j695", "GHSA-p6mc-m468-83gw"]
+    );
+    assert!(osv_advisories(&["osv/missing.zip"], "npm", "lodash", "4.17.11").is_empty());
+}
+
+#[test]
+fn osv_pypi() {
+    let django = |version| osv_advisories(&["osv/PyPI"], "pypi", "Django", version);
+    let advisories = django("2.2.3");
+    assert_eq!(ids(&advisories), vec!["PYSEC-2019-79"]);
+    assert!(advisories[0].severity == Severity::High);
+    assert_eq!(advisories[0].aliases, vec!["CVE-2019-14232"]);
+    // The first line of the details when there is no summary
+    assert_eq!(
+        advisories[0].title,
+        "An issue was discovered in Django 1.11.x before 1.11.23, 2.1.x before 2.1.11, and 2.2.x before 2.2.4."
+    );
+    assert_eq!(advisories[0].patched, vec![">= 2.1.11", ">= 2.2.4"]);
+    // Versions compared as PEP 440 ones, 2.2 is 2.2.0
+    assert_eq!(django("2.2.0").len(), 1);
+    assert_eq!(django("2.2rc1").len(), 0);
+    assert_eq!(django("2.1.10").len(), 1);
+    assert!(django("2.1.11").is_empty());
+    assert!(django("2.2.4").is_empty());
+    assert!(django("2.0").is_empty());
+
+    let requests = osv_advisories(&["osv/PyPI"], "pypi", "requests", "2.19.1");
+    assert_eq!(ids(&requests), vec!["PYSEC-2018-28"]);
+    assert!(requests[0].severity == Severity::Unknown);
+    assert!(requests[0].patched.is_empty());
+    assert!(osv_advisories(&["osv/PyPI"], "pypi", "requests", "2.20.0").is_empty());
+
+    // Names compared normalized
+    let flask_cors = |version| osv_advisories(&["osv/PyPI"], "pypi", "flask-cors", version);
+    assert!(flask_cors("3.0.0b2")[0].severity == Severity::Moderate);
+    assert!(flask_cors("3.0.0rc1").is_empty());
+    assert!(flask_cors("2.1.3").is_empty());
+}
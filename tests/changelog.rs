@@ -0,0 +1,102 @@
+use neovim_package_info::changelog::{find, find_in_tarball, find_vendored, sections};
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn fixture_path(path: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/changelog")
+        .join(path)
+}
+
+fn fixture(path: &str) -> String {
+    fs::read_to_string(fixture_path(path)).unwrap()
+}
+
+#[test]
+fn markdown_sections() {
+    let changelog = fixture("CHANGELOG.md");
+    // The unreleased section and the locked version are left out, deeper headings are kept
+    assert_eq!(
+        sections(&changelog, "1.0.90", "1.0.92").unwrap(),
+        vec![
+            "## [1.0.92] - 2019-06-02",
+            "",
+            "### Fixed",
+            "",
+            "- Fix deserialization of borrowed strings",
+            "",
+            "## [1.0.91] - 2019-05-13",
+            "",
+            "- Support `#[serde(flatten)]` on enums",
+            "",
+        ]
+    );
+    // Versions in headings can leave out the patch number, and have a v prefix
+    let older = sections(&changelog, "0.9.15", "1.0.90").unwrap();
+    assert_eq!(older[0], "## v1.0.90");
+    assert_eq!(older[older.len() - 1], "- First stable release");
+    assert!(sections(&changelog, "1.0.92", "1.0.92").unwrap().is_empty());
+}
+
+#[test]
+fn setext_sections() {
+    let changelog = fixture("HISTORY.md");
+    let history = sections(&changelog, "2.20.1", "2.22").unwrap();
+    assert_eq!(history[0], "2.22.0 (2019-05-15)");
+    assert!(history.contains(&"- Requests now supports idna v2.8.".to_string()));
+    assert!(!history.iter().any(|line| line.contains("Authorization")));
+    // Without version headings the changelog can't be split
+    assert!(sections(&fixture("NOTES.md"), "1.0.0", "2.0.0").is_none());
+}
+
+#[test]
+fn changelog_in_tarball() {
+    let tarball = fs::read(fixture_path("serde-1.0.92.crate")).unwrap();
+    // At the top of the package only, CHANGELOG before RELEASES whatever the case
+    assert_eq!(find_in_tarball(&tarball).unwrap(), fixture("CHANGELOG.md"));
+    assert!(find_in_tarball(b"not an archive").is_none());
+}
+
+#[test]
+fn vendored_changelogs() {
+    env::set_var("CARGO_HOME", fixture_path("cargo-home"));
+    let project = fixture_path("project");
+
+    // The sources cargo extracted, of that version only
+    let (path, content) = find_vendored("cargo", "serde", "1.0.91", &project).unwrap();
+    assert!(path.ends_with("serde-1.0.91/CHANGELOG.md"));
+    assert!(content.contains("## [1.0.91]"));
+    assert!(find_vendored("cargo", "serde", "1.0.92", &project).is_none());
+
+    // The installed package, when it's the version asked for
+    let (path, _) = find_vendored("npm", "react", "16.8.6", &project).unwrap();
+    assert_eq!(path, project.join("node_modules/react/CHANGELOG.md"));
+    assert!(find_vendored("npm", "react", "16.9.0", &project).is_none());
+    assert!(find_vendored("npm", "vue", "2.6.10", &project).is_none());
+    assert!(find_vendored("pypi", "requests", "2.22.0", Path::new(".")).is_none());
+}
+
+#[test]
+fn latest_changelog_first() {
+    env::set_var("CARGO_HOME", fixture_path("cargo-home"));
+    let project = fixture_path("project");
+    let tarball = || fs::read(fixture_path("serde-1.0.92.crate")).ok();
+
+    // The sources of the locked version end before the update, the archive of the latest doesn't
+    let (source, content) = find("cargo", "serde", "1.0.92", "1.0.91", &project, tarball).unwrap();
+    assert_eq!(source, "serde 1.0.92 archive");
+    assert_eq!(
+        sections(&content, "1.0.91", "1.0.92").unwrap()[0],
+        "## [1.0.92] - 2019-06-02"
+    );
+
+    // Better than nothing when the archive can't be downloaded
+    let (source, _) = find("cargo", "serde", "1.0.92", "1.0.91", &project, || None).unwrap();
+    assert!(source.ends_with("serde-1.0.91/CHANGELOG.md"));
+
+    // Vendored copies of the latest version don't need a download
+    let (source, _) = find("npm", "react", "16.8.6", "16.8.5", &project, || panic!()).unwrap();
+    assert!(source.ends_with("react/CHANGELOG.md"));
+}
@@ -0,0 +1,152 @@
+use neovim_package_info::neovim::Config;
+
+use std::env;
+use std::fs;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, Once};
+use std::thread;
+
+static ENVIRONMENT: Once = Once::new();
+
+// A request received by the mock registry
+#[derive(Clone)]
+pub struct Request {
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/* A registry stand-in listening on a local port, answering each path with the
+ * response it was given, and with a 404 otherwise.
+ * Requests are recorded so tests can check what the stores sent.
+ */
+pub struct MockRegistry {
+    pub url: String,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl MockRegistry {
+    pub fn start(routes: &[(&str, u16, &str)]) -> Self {
+        setup_environment();
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Error binding the mock registry");
+        let address = listener.local_addr().unwrap();
+        // Start from an empty disk cache, ports are reused between runs
+        let host = address.to_string().replace(':', "%3A");
+        let cached = fs::read_dir(cache_dir().join("neovim-package-info"))
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                name == host || name.starts_with(&format!("{}%2F", host))
+            });
+        for entry in cached {
+            let _ = fs::remove_dir_all(entry.path());
+        }
+
+        let routes: Vec<(String, u16, String)> = routes
+            .iter()
+            .map(|(path, status, body)| (path.to_string(), *status, body.to_string()))
+            .collect();
+        let requests = Arc::new(Mutex::new(vec![]));
+        let recorded = requests.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().filter_map(|stream| stream.ok()) {
+                if let Some(request) = read_request(&stream) {
+                    let (status, body) = routes
+                        .iter()
+                        .find(|(path, _, _)| *path == request.path)
+                        .map(|(_, status, body)| (*status, body.as_str()))
+                        .unwrap_or((404, "{\"errors\": [{\"detail\": \"Not Found\"}]}"));
+                    // Record first, the store may check the requests as soon as it gets the response
+                    recorded.lock().unwrap().push(request);
+                    write_response(&stream, status, body);
+                }
+            }
+        });
+        MockRegistry {
+            url: format!("http://{}", address),
+            requests,
+        }
+    }
+
+    // Requests received so far for a path
+    pub fn requests(&self, path: &str) -> Vec<Request> {
+        let requests = self.requests.lock().unwrap();
+        requests
+            .iter()
+            .filter(|request| request.path == path)
+            .cloned()
+            .collect()
+    }
+}
+
+fn read_request(mut stream: &TcpStream) -> Option<Request> {
+    // Stores only send GET requests, there is no body to read after the headers
+    let mut content = vec![];
+    let mut buffer = [0; 1024];
+    while !content.windows(4).any(|window| window == b"\r\n\r\n") {
+        let read = stream.read(&mut buffer).ok()?;
+        if read == 0 {
+            return None;
+        }
+        content.extend_from_slice(&buffer[..read]);
+    }
+    let content = String::from_utf8_lossy(&content);
+    let mut lines = content.lines();
+    let path = lines.next()?.split(' ').nth(1)?.to_string();
+    let headers = lines
+        .filter_map(|line| line.split_once(": "))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+    Some(Request { path, headers })
+}
+
+fn write_response(mut stream: &TcpStream, status: u16, body: &str) {
+    let response = format!(
+        "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes());
+}
+
+fn cache_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("cache")
+}
+
+// Keep the disk cache away from the user's one, and the requests away from any proxy
+fn setup_environment() {
+    ENVIRONMENT.call_once(|| {
+        env::set_var("XDG_CACHE_HOME", cache_dir());
+        env::set_var("NO_PROXY", "127.0.0.1");
+    });
+}
+
+// Read a canned registry response
+pub fn fixture(path: &str) -> String {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(path);
+    fs::read_to_string(&path).unwrap_or_else(|_| panic!("Missing fixture {:?}", path))
+}
+
+// Fail right away, tests don't need to wait for retries
+pub fn config() -> Config {
+    Config {
+        retries: 0,
+        timeout: 5,
+        ..Config::default()
+    }
+}
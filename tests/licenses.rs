@@ -0,0 +1,100 @@
+use neovim_package_info::license::{Expression, Policy};
+
+fn policy(allow: &[&str], deny: &[&str]) -> Policy {
+    Policy {
+        allow: allow.iter().map(|license| license.to_string()).collect(),
+        deny: deny.iter().map(|license| license.to_string()).collect(),
+    }
+}
+
+fn license(id: &str) -> Box<Expression> {
+    Box::new(Expression::License(id.to_string(), None))
+}
+
+#[test]
+fn expressions() {
+    assert_eq!(
+        Expression::parse("MIT OR Apache-2.0 AND BSD-3-Clause"),
+        Some(Expression::Or(
+            license("MIT"),
+            Box::new(Expression::And(
+                license("Apache-2.0"),
+                license("BSD-3-Clause")
+            ))
+        ))
+    );
+    assert_eq!(
+        Expression::parse("(MIT or Apache-2.0) AND Zlib"),
+        Some(Expression::And(
+            Box::new(Expression::Or(license("MIT"), license("Apache-2.0"))),
+            license("Zlib")
+        ))
+    );
+    assert_eq!(
+        Expression::parse("MIT/Apache-2.0"),
+        Some(Expression::Or(license("MIT"), license("Apache-2.0")))
+    );
+    assert_eq!(
+        Expression::parse("GPL-2.0-or-later WITH Classpath-exception-2.0"),
+        Some(Expression::License(
+            "GPL-2.0-or-later".to_string(),
+            Some("Classpath-exception-2.0".to_string())
+        ))
+    );
+    assert_eq!(Expression::parse("(MIT"), None);
+    assert_eq!(Expression::parse("MIT AND"), None);
+    assert_eq!(Expression::parse("MIT Apache-2.0"), None);
+    assert_eq!(Expression::parse(""), None);
+}
+
+#[test]
+fn deny_list() {
+    let policy = policy(&[], &["GPL-*", "AGPL-3.0-only"]);
+    assert_eq!(policy.violation(Some("MIT")), None);
+    // Another license can be picked
+    assert_eq!(policy.violation(Some("MIT OR GPL-3.0-only")), None);
+    assert_eq!(
+        policy.violation(Some("MIT AND GPL-3.0-only")),
+        Some("license MIT AND GPL-3.0-only is denied (GPL-3.0-only)".to_string())
+    );
+    assert!(policy.violation(Some("gpl-2.0+")).is_some());
+    assert!(policy
+        .violation(Some("(AGPL-3.0-only OR GPL-3.0-only)"))
+        .is_some());
+    // Not a valid expression, checked as a whole
+    assert!(policy.violation(Some("GPL-3.0 (see LICENSE)")).is_some());
+    assert!(policy.violation(Some("MIT (see LICENSE)")).is_none());
+    assert!(policy.violation(Some("LGPL-3.0-only")).is_none());
+    assert_eq!(policy.violation(None), None);
+}
+
+#[test]
+fn allow_list() {
+    let policy = policy(&["MIT", "Apache-2.0", "BSD-*"], &["BSD-4-Clause"]);
+    assert_eq!(policy.violation(Some("MIT OR Apache-2.0")), None);
+    assert_eq!(policy.violation(Some("BSD-3-Clause")), None);
+    assert_eq!(
+        policy.violation(Some("MPL-2.0")),
+        Some("license MPL-2.0 is not allowed".to_string())
+    );
+    // Denied even if allowed by a pattern
+    assert!(policy.violation(Some("BSD-4-Clause")).is_some());
+    assert!(policy.violation(Some("MIT AND MPL-2.0")).is_some());
+    assert_eq!(policy.violation(None), Some("unknown license".to_string()));
+    assert_eq!(Policy::default().violation(None), None);
+}
+
+#[test]
+fn exceptions() {
+    let policy = policy(
+        &["Apache-2.0", "GPL-2.0-only WITH Classpath-exception-2.0"],
+        &[],
+    );
+    assert!(policy
+        .violation(Some("GPL-2.0-only WITH Classpath-exception-2.0"))
+        .is_none());
+    assert!(policy.violation(Some("GPL-2.0-only")).is_some());
+    assert!(policy
+        .violation(Some("Apache-2.0 WITH LLVM-exception"))
+        .is_none());
+}
@@ -0,0 +1,271 @@
+use neovim_package_info::neovim::DependencyInfo;
+use neovim_package_info::parser::{CargoParser, Drift, PackageJsonParser, Parser, PipfileParser};
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+fn read(path: &str) -> String {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(path);
+    fs::read_to_string(&path).unwrap_or_else(|_| panic!("Missing fixture {:?}", path))
+}
+
+/* Compare the parsed dependencies with the golden file, one dependency per line.
+ * Run with UPDATE_GOLDEN=1 to write the golden files from the current output instead.
+ */
+fn assert_golden(dependencies: Vec<DependencyInfo>, golden: &str) {
+    let actual: String = dependencies
+        .iter()
+        .map(|dep| {
+            // Dependencies that aren't locked have no version
+            let current = match &dep.current[..] {
+                "" => "-",
+                current => current,
+            };
+            format!(
+                "{} {} {} {}\n",
+                dep.line_number, dep.name, dep.requirement, current
+            )
+        })
+        .collect();
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/parser")
+        .join(golden);
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&path, &actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("Missing golden file {:?}, run with UPDATE_GOLDEN=1", path));
+    assert_eq!(
+        actual, expected,
+        "Parsed dependencies differ from {:?}",
+        path
+    );
+}
+
+#[test]
+fn cargo_example() {
+    let dependencies =
+        CargoParser::get_dependencies(&read("examples/Cargo.toml"), &read("examples/Cargo.lock"))
+            .unwrap();
+    assert_golden(dependencies, "cargo_example.golden");
+}
+
+#[test]
+fn cargo_edge_cases() {
+    let dependencies = CargoParser::get_dependencies(
+        &read("tests/fixtures/parser/cargo/Cargo.toml"),
+        &read("tests/fixtures/parser/cargo/Cargo.lock"),
+    )
+    .unwrap();
+    assert_golden(dependencies, "cargo_edge_cases.golden");
+}
+
+#[test]
+fn cargo_missing_lockfile() {
+    let dependencies =
+        CargoParser::get_dependencies(&read("tests/fixtures/parser/cargo/Cargo.toml"), "").unwrap();
+    assert_golden(dependencies, "cargo_missing_lockfile.golden");
+}
+
+#[test]
+fn pipfile_example() {
+    let dependencies =
+        PipfileParser::get_dependencies(&read("examples/Pipfile"), &read("examples/Pipfile.lock"))
+            .unwrap();
+    assert_golden(dependencies, "pipfile_example.golden");
+}
+
+#[test]
+fn pipfile_edge_cases() {
+    let dependencies =
+        PipfileParser::get_dependencies(&read("tests/fixtures/parser/pipfile/Pipfile"), "")
+            .unwrap();
+    assert_golden(dependencies, "pipfile_edge_cases.golden");
+}
+
+#[test]
+fn package_json_example() {
+    let dependencies = PackageJsonParser::get_dependencies(
+        &read("examples/package.json"),
+        &read("examples/yarn.lock"),
+    )
+    .unwrap();
+    assert_golden(dependencies, "package_json_example.golden");
+}
+
+#[test]
+fn package_json_edge_cases() {
+    let dependencies = PackageJsonParser::get_dependencies(
+        &read("tests/fixtures/parser/package_json/package.json"),
+        "",
+    )
+    .unwrap();
+    assert_golden(dependencies, "package_json_edge_cases.golden");
+}
+
+#[test]
+fn cargo_rust_version() {
+    let manifest = "[package]\nname = \"msrv\"\nrust-version = \"1.70\"\n";
+    assert_eq!(
+        CargoParser::get_toolchain(manifest),
+        Some("1.70".to_string())
+    );
+    // Inherited from the workspace
+    let manifest = "[package]\nname = \"msrv\"\nrust-version.workspace = true\n";
+    assert_eq!(CargoParser::get_toolchain(manifest), None);
+    assert_eq!(
+        CargoParser::get_toolchain(&read("examples/Cargo.toml")),
+        None
+    );
+}
+
+#[test]
+fn pipfile_python_version() {
+    let pipfile = "[requires]\npython_version = \"3.8\"\n";
+    assert_eq!(
+        PipfileParser::get_toolchain(pipfile),
+        Some("3.8".to_string())
+    );
+    let pipfile = "[requires]\npython_version = \"3.8\"\npython_full_version = \"3.8.10\"\n";
+    assert_eq!(
+        PipfileParser::get_toolchain(pipfile),
+        Some("3.8.10".to_string())
+    );
+    assert_eq!(PipfileParser::get_toolchain("[packages]\n"), None);
+}
+
+#[test]
+fn package_json_engines() {
+    let package_json = r#"{"engines": {"node": ">=18", "npm": ">=9"}}"#;
+    assert_eq!(
+        PackageJsonParser::get_toolchain(package_json),
+        Some(">=18".to_string())
+    );
+    assert_eq!(
+        PackageJsonParser::get_toolchain(&read("examples/package.json")),
+        None
+    );
+}
+
+#[test]
+fn installed_versions() {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/parser");
+
+    // Read from node_modules, @babel/core isn't installed
+    let mut dependencies = PackageJsonParser::get_dependencies(
+        &read("tests/fixtures/parser/package_json/package.json"),
+        "",
+    )
+    .unwrap();
+    PackageJsonParser::resolve_installed(&mut dependencies, &dir.join("package_json"));
+    let versions: Vec<(&str, &str)> = dependencies
+        .iter()
+        .map(|dep| (dep.name.as_str(), dep.current.as_str()))
+        .collect();
+    assert_eq!(
+        versions,
+        vec![
+            ("@babel/core", ""),
+            ("@types/node", "12.0.4"),
+            ("lodash", "4.17.11")
+        ]
+    );
+
+    // Read from the dist-info of the virtualenv in the project, names compared normalized
+    env::remove_var("VIRTUAL_ENV");
+    let mut dependencies =
+        PipfileParser::get_dependencies(&read("tests/fixtures/parser/pipfile/Pipfile"), "")
+            .unwrap();
+    PipfileParser::resolve_installed(&mut dependencies, &dir.join("pipfile"));
+    let versions: Vec<(&str, &str)> = dependencies
+        .iter()
+        .map(|dep| (dep.name.as_str(), dep.current.as_str()))
+        .collect();
+    assert_eq!(
+        versions,
+        vec![
+            ("Flask", "1.0.2"),
+            ("click", ""),
+            ("Jinja2", ""),
+            ("typing_extensions", "4.7.1")
+        ]
+    );
+
+    // Locked versions are kept
+    let mut dependencies =
+        PipfileParser::get_dependencies(&read("examples/Pipfile"), &read("examples/Pipfile.lock"))
+            .unwrap();
+    let locked: Vec<String> = dependencies.iter().map(|dep| dep.current.clone()).collect();
+    PipfileParser::resolve_installed(&mut dependencies, &dir.join("pipfile"));
+    for (dep, locked) in dependencies.iter().zip(locked) {
+        if !locked.is_empty() {
+            assert_eq!(dep.current, locked);
+        }
+    }
+}
+
+#[test]
+fn lockfile_drift() {
+    let manifest = read("tests/fixtures/parser/drift/cargo/Cargo.toml");
+    let lockfile = read("tests/fixtures/parser/drift/cargo/Cargo.lock");
+    // Only the root package dependencies count, platform specific ones too,
+    // and renamed ones are locked under their package name
+    assert_eq!(
+        CargoParser::get_drift(&manifest, &lockfile)
+            .unwrap()
+            .unwrap(),
+        vec![
+            Drift::Unlocked {
+                name: "regex".to_string(),
+                line_number: 8
+            },
+            Drift::Unused("log".to_string()),
+        ]
+    );
+
+    // Entries other entries depend on are not the manifest's
+    let manifest = read("tests/fixtures/parser/drift/yarn/package.json");
+    let lockfile = read("tests/fixtures/parser/drift/yarn/yarn.lock");
+    assert_eq!(
+        PackageJsonParser::get_drift(&manifest, &lockfile)
+            .unwrap()
+            .unwrap(),
+        vec![
+            Drift::Unlocked {
+                name: "react".to_string(),
+                line_number: 5
+            },
+            Drift::Unused("left-pad".to_string()),
+        ]
+    );
+
+    let manifest = read("tests/fixtures/parser/drift/pipfile/Pipfile");
+    let lockfile = read("tests/fixtures/parser/drift/pipfile/Pipfile.lock");
+    assert_eq!(
+        PipfileParser::get_drift(&manifest, &lockfile)
+            .unwrap()
+            .unwrap(),
+        vec![Drift::Unlocked {
+            name: "pytest".to_string(),
+            line_number: 11
+        }]
+    );
+    // The hash pipenv computed no longer matches once the Pipfile changes
+    let changed = manifest.replace("==2.22.0", "==2.21.0");
+    assert_eq!(
+        PipfileParser::get_drift(&changed, &lockfile)
+            .unwrap()
+            .unwrap()[0],
+        Drift::HashMismatch
+    );
+    // As locked by pipenv itself
+    assert!(!PipfileParser::is_hash_mismatch(
+        &read("examples/Pipfile"),
+        &read("examples/Pipfile.lock")
+    ));
+
+    // Nothing to drift from without a lockfile
+    assert!(PipfileParser::get_drift(&manifest, "").unwrap().is_none());
+}
@@ -0,0 +1,52 @@
+use neovim_package_info::python::Version;
+
+#[test]
+fn pep440_ordering() {
+    let versions = [
+        "1.0.dev1",
+        "1.0a1",
+        "1.0a2.dev1",
+        "1.0a2",
+        "1.0b1",
+        "1.0rc1",
+        "1.0",
+        "1.0.post1",
+        "1.0.1",
+        "1.1",
+        "1!0.1",
+    ];
+    let parsed: Vec<Version> = versions
+        .iter()
+        .map(|version| Version::parse(version).unwrap())
+        .collect();
+    for pair in parsed.windows(2) {
+        assert!(pair[0] < pair[1], "{:?} < {:?}", pair[0], pair[1]);
+    }
+    assert_eq!(Version::parse("1.0.0"), Version::parse("1"));
+    assert_eq!(Version::parse("1.0-1"), Version::parse("1.0.post1"));
+    assert_eq!(Version::parse("v1.0RC1"), Version::parse("1.0rc1"));
+    assert!(Version::parse("1.0rc1").unwrap().is_prerelease());
+    assert!(!Version::parse("1.0.post1").unwrap().is_prerelease());
+    assert!(Version::parse("not a version").is_none());
+}
+
+#[test]
+fn pep440_specifiers() {
+    let satisfies =
+        |version: &str, specifiers: &str| Version::parse(version).unwrap().satisfies(specifiers);
+    assert_eq!(satisfies("3.8", ">=3.8"), Some(true));
+    assert_eq!(satisfies("3.8", ">=3.6, <4"), Some(true));
+    assert_eq!(satisfies("3.8", ">=3.10"), Some(false));
+    assert_eq!(satisfies("3.8", ">=3.8.1"), Some(false));
+    assert_eq!(satisfies("3.10.2", ">3.9"), Some(true));
+    assert_eq!(satisfies("2.7", ">=2.7, !=3.0.*, !=3.1.*"), Some(true));
+    assert_eq!(satisfies("3.1.4", ">=2.7, !=3.0.*, !=3.1.*"), Some(false));
+    assert_eq!(satisfies("3.8.5", "==3.8.*"), Some(true));
+    assert_eq!(satisfies("3.9", "==3.8.*"), Some(false));
+    assert_eq!(satisfies("3.8.5", "~=3.8.1"), Some(true));
+    assert_eq!(satisfies("3.9", "~=3.8.1"), Some(false));
+    assert_eq!(satisfies("3.9", "~=3.8"), Some(true));
+    assert_eq!(satisfies("3.8", ""), Some(true));
+    assert_eq!(satisfies("3.8", ">=three"), None);
+    assert_eq!(satisfies("3.8", "^3.8"), None);
+}
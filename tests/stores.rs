@@ -0,0 +1,676 @@
+mod common;
+
+use common::{config, fixture, MockRegistry};
+use neovim_package_info::consts;
+use neovim_package_info::neovim::Config;
+use neovim_package_info::neovim::DependencyInfo;
+use neovim_package_info::parser::{Parser, PipfileParser};
+use neovim_package_info::store::{
+    check_dependency, date, newest_compatible, timestamp, Cratesio, Npm, Pypi, Registry, Store,
+    StoreError, UpdateKind,
+};
+
+fn dependency(name: &str, current: &str) -> DependencyInfo {
+    DependencyInfo {
+        name: name.to_string(),
+        requirement: current.to_string(),
+        current: current.to_string(),
+        latest: vec![],
+        update: None,
+        license: None,
+        released: None,
+        last_release: None,
+        line_number: 0,
+    }
+}
+
+fn with_token(url: &str, token: &str) -> Registry {
+    Registry {
+        token: Some(token.to_string()),
+        ..Registry::new(url)
+    }
+}
+
+#[test]
+fn cratesio_latest_and_versions() {
+    let serde = fixture("registry/cratesio/serde.json");
+    let registry = MockRegistry::start(&[("/api/v1/crates/serde", 200, &serde)]);
+    let store = Cratesio::new(Registry::new(&format!("{}/api/v1/crates", registry.url)));
+
+    let package = store.get_package("serde", &config()).unwrap();
+    assert_eq!(package.max_version, "1.0.92");
+    // Yanked versions are not upgrade candidates
+    assert_eq!(package.versions, vec!["1.0.92", "1.0.91", "0.9.15"]);
+
+    let (latest, update) = check_dependency(
+        &store,
+        &dependency("serde", "1.0.91"),
+        Ok(&package),
+        &config(),
+    );
+    let update = update.unwrap();
+    assert!(update.kind == UpdateKind::Patch);
+    assert_eq!(update.version, "1.0.92");
+    assert_eq!(
+        latest,
+        vec![
+            (" -> 1.0.".to_string(), consts::UP_TO_DATE_HG.to_string()),
+            ("92".to_string(), consts::PATCH_HG.to_string()),
+        ]
+    );
+}
+
+#[test]
+fn cratesio_up_to_date() {
+    let serde = fixture("registry/cratesio/serde.json");
+    let registry = MockRegistry::start(&[("/serde", 200, &serde)]);
+    let store = Cratesio::new(Registry::new(&registry.url));
+
+    let package = store.get_package("serde", &config()).unwrap();
+    let (latest, update) = check_dependency(
+        &store,
+        &dependency("serde", "1.0.92"),
+        Ok(&package),
+        &config(),
+    );
+    assert!(latest.is_empty());
+    assert!(update.is_none());
+}
+
+#[test]
+fn cratesio_not_found() {
+    let registry = MockRegistry::start(&[]);
+    let store = Cratesio::new(Registry::new(&registry.url));
+
+    let error = store.get_package("missing", &config()).err().unwrap();
+    assert!(matches!(error, StoreError::NotFound));
+
+    let (latest, update) = check_dependency(
+        &store,
+        &dependency("missing", "1.0.0"),
+        Err(&error),
+        &config(),
+    );
+    assert!(update.is_none());
+    assert_eq!(
+        latest,
+        vec![(
+            " -> missing not found".to_string(),
+            consts::NOT_FOUND_HG.to_string()
+        )]
+    );
+}
+
+#[test]
+fn registries_on_the_same_host() {
+    let serde = fixture("registry/cratesio/serde.json");
+    let registry = MockRegistry::start(&[("/public/serde", 200, &serde)]);
+    let public = Cratesio::new(Registry::new(&format!("{}/public", registry.url)));
+    let private = Cratesio::new(Registry::new(&format!("{}/private", registry.url)));
+
+    assert!(public.get_package("serde", &config()).is_ok());
+    // The package cached for the other registry isn't served
+    let error = private.get_package("serde", &config()).err().unwrap();
+    assert!(matches!(error, StoreError::NotFound));
+    assert_eq!(registry.requests("/private/serde").len(), 1);
+}
+
+#[test]
+fn cratesio_malformed_json() {
+    let broken = fixture("registry/cratesio/broken.json");
+    let registry = MockRegistry::start(&[("/broken", 200, &broken)]);
+    let store = Cratesio::new(Registry::new(&registry.url));
+
+    let error = store.get_package("broken", &config()).err().unwrap();
+    assert!(matches!(error, StoreError::Parse(_)));
+
+    let (latest, _) = check_dependency(
+        &store,
+        &dependency("broken", "1.0.0"),
+        Err(&error),
+        &config(),
+    );
+    assert_eq!(latest[0].1, consts::PARSE_ERROR_HG);
+}
+
+#[test]
+fn cratesio_rate_limited() {
+    let registry = MockRegistry::start(&[("/serde", 429, "{}")]);
+    let store = Cratesio::new(Registry::new(&registry.url));
+
+    let error = store.get_package("serde", &config()).err().unwrap();
+    assert!(matches!(error, StoreError::RateLimited));
+}
+
+#[test]
+fn cratesio_client_errors() {
+    let registry = MockRegistry::start(&[("/serde", 403, "{}"), ("/tokio", 410, "{}")]);
+    let store = Cratesio::new(Registry::new(&registry.url));
+    let config = Config {
+        retries: 2,
+        ..config()
+    };
+
+    let error = store.get_package("serde", &config).err().unwrap();
+    assert!(matches!(error, StoreError::Unauthorized));
+    let error = store.get_package("tokio", &config).err().unwrap();
+    assert!(matches!(error, StoreError::Network(_)));
+    // Neither is retried nor cached
+    assert!(store.get_package("tokio", &config).is_err());
+    assert_eq!(registry.requests("/serde").len(), 1);
+    assert_eq!(registry.requests("/tokio").len(), 2);
+}
+
+#[test]
+fn cratesio_pre_releases() {
+    let tokio = fixture("registry/cratesio/tokio.json");
+    let registry = MockRegistry::start(&[("/tokio", 200, &tokio)]);
+    let store = Cratesio::new(Registry::new(&registry.url));
+    let package = store.get_package("tokio", &config()).unwrap();
+
+    // The registry advertises a pre-release as its latest version
+    let (latest, update) = check_dependency(
+        &store,
+        &dependency("tokio", "0.1.21"),
+        Ok(&package),
+        &config(),
+    );
+    let update = update.unwrap();
+    assert!(update.kind == UpdateKind::Minor);
+    assert_eq!(update.version, "0.2.0-alpha.1");
+    assert_eq!(latest[1].0, "2.0-alpha.1");
+
+    // Already on that pre-release
+    let (latest, update) = check_dependency(
+        &store,
+        &dependency("tokio", "0.2.0-alpha.1"),
+        Ok(&package),
+        &config(),
+    );
+    assert!(latest.is_empty());
+    assert!(update.is_none());
+
+    // On an older pre-release of the same version
+    let (_, update) = check_dependency(
+        &store,
+        &dependency("tokio", "0.2.0-alpha.0"),
+        Ok(&package),
+        &config(),
+    );
+    assert!(update.unwrap().kind == UpdateKind::Patch);
+
+    // On a pre-release newer than the latest version
+    let (latest, update) = check_dependency(
+        &store,
+        &dependency("tokio", "0.3.0-alpha.1"),
+        Ok(&package),
+        &config(),
+    );
+    assert!(latest.is_empty());
+    assert!(update.is_none());
+}
+
+#[test]
+fn cratesio_token() {
+    let serde = fixture("registry/cratesio/serde.json");
+    let registry = MockRegistry::start(&[("/serde", 200, &serde)]);
+    let store = Cratesio::new(with_token(&registry.url, "secret"));
+
+    store.get_package("serde", &config()).unwrap();
+    let requests = registry.requests("/serde");
+    assert_eq!(requests[0].header("authorization"), Some("secret"));
+}
+
+#[test]
+fn npm_latest_and_versions() {
+    let react = fixture("registry/npm/react.json");
+    let registry = MockRegistry::start(&[("/react", 200, &react)]);
+    let store = Npm::new(Registry::new(&registry.url));
+
+    let package = store.get_package("react", &config()).unwrap();
+    // The latest tag, not the highest pre-release
+    assert_eq!(package.max_version, "16.8.6");
+    assert!(package.versions.contains(&"16.9.0-alpha.0".to_string()));
+
+    let (_, update) = check_dependency(
+        &store,
+        &dependency("react", "16.8.5"),
+        Ok(&package),
+        &config(),
+    );
+    assert_eq!(update.unwrap().version, "16.8.6");
+
+    // Abbreviated metadata is asked for
+    let requests = registry.requests("/react");
+    let accept = requests[0].header("accept").unwrap();
+    assert!(accept.starts_with("application/vnd.npm.install-v1+json"));
+}
+
+#[test]
+fn npm_scoped_package() {
+    let types_node = fixture("registry/npm/types-node.json");
+    let registry = MockRegistry::start(&[("/@types/node", 200, &types_node)]);
+    let store = Npm::new(with_token(&registry.url, "secret"));
+
+    let package = store.get_package("@types/node", &config()).unwrap();
+    assert_eq!(package.max_version, "12.0.4");
+
+    let (latest, update) = check_dependency(
+        &store,
+        &dependency("@types/node", "11.13.13"),
+        Ok(&package),
+        &config(),
+    );
+    assert!(update.unwrap().kind == UpdateKind::Major);
+    assert_eq!(
+        latest,
+        vec![(" -> 12.0.4".to_string(), consts::MAJOR_HG.to_string())]
+    );
+
+    let requests = registry.requests("/@types/node");
+    assert_eq!(requests[0].header("authorization"), Some("Bearer secret"));
+}
+
+#[test]
+fn npm_not_found() {
+    let registry = MockRegistry::start(&[]);
+    let store = Npm::new(Registry::new(&registry.url));
+
+    let error = store
+        .get_package("@scope/missing", &config())
+        .err()
+        .unwrap();
+    assert!(matches!(error, StoreError::NotFound));
+}
+
+#[test]
+fn pypi_latest_and_versions() {
+    let requests = fixture("registry/pypi/requests.json");
+    let registry = MockRegistry::start(&[("/pypi/requests/json", 200, &requests)]);
+    let store = Pypi::new(Registry::new(&format!("{}/pypi", registry.url)));
+
+    let package = store.get_package("requests", &config()).unwrap();
+    assert_eq!(package.max_version, "2.22.0");
+    assert_eq!(package.versions.len(), 3);
+
+    let (_, update) = check_dependency(
+        &store,
+        &dependency("requests", "2.21.0"),
+        Ok(&package),
+        &config(),
+    );
+    let update = update.unwrap();
+    assert!(update.kind == UpdateKind::Minor);
+    assert_eq!(update.version, "2.22.0");
+}
+
+#[test]
+fn pypi_not_found_and_malformed_json() {
+    let registry = MockRegistry::start(&[("/broken/json", 200, "<html>")]);
+    let store = Pypi::new(Registry::new(&registry.url));
+
+    let error = store.get_package("missing", &config()).err().unwrap();
+    assert!(matches!(error, StoreError::NotFound));
+    let error = store.get_package("broken", &config()).err().unwrap();
+    assert!(matches!(error, StoreError::Parse(_)));
+}
+
+#[test]
+fn pypi_basic_authentication() {
+    let requests = fixture("registry/pypi/requests.json");
+    let registry = MockRegistry::start(&[("/requests/json", 200, &requests)]);
+    let store = Pypi::new(with_token(&registry.url, "user:pass"));
+
+    store.get_package("requests", &config()).unwrap();
+    let requests = registry.requests("/requests/json");
+    assert_eq!(
+        requests[0].header("authorization"),
+        Some("Basic dXNlcjpwYXNz")
+    );
+}
+
+#[test]
+fn licenses() {
+    let serde = fixture("registry/cratesio/serde.json");
+    let react = fixture("registry/npm/react.json");
+    let requests = fixture("registry/pypi/requests.json");
+    let requests_release = fixture("registry/pypi/requests-2.21.0.json");
+    let registry = MockRegistry::start(&[
+        ("/serde", 200, &serde),
+        ("/react", 200, &react),
+        ("/requests/json", 200, &requests),
+        ("/requests/2.21.0/json", 200, &requests_release),
+    ]);
+
+    let package = Cratesio::new(Registry::new(&registry.url))
+        .get_package("serde", &config())
+        .unwrap();
+    assert_eq!(package.license("1.0.91"), Some("MIT OR Apache-2.0"));
+    assert_eq!(package.license("0.9.15"), Some("MIT/Apache-2.0"));
+    assert_eq!(package.license("2.0.0"), None);
+
+    // Licenses are not in the abbreviated npm metadata, the full one is asked for
+    // even when the abbreviated one is cached
+    Npm::new(Registry::new(&registry.url))
+        .get_package("react", &config())
+        .unwrap();
+    let config = Config {
+        fields: vec!["current".to_string(), "license".to_string()],
+        registries: vec![("npm".to_string(), Registry::new(&registry.url))]
+            .into_iter()
+            .collect(),
+        ..config()
+    };
+    let package = Npm::from_config(&config)
+        .get_package("react", &config)
+        .unwrap();
+    assert_eq!(package.license("16.8.6"), Some("MIT"));
+    assert_eq!(package.license("16.8.5"), Some("MIT"));
+    assert_eq!(package.license("16.9.0-alpha.0"), None);
+    assert_eq!(registry.requests("/react").len(), 2);
+    let accept = registry.requests("/react")[1]
+        .header("accept")
+        .unwrap()
+        .to_string();
+    assert_eq!(accept, "application/json");
+
+    // The classifier rather than the free text license
+    let store = Pypi::new(Registry::new(&registry.url));
+    let package = store.get_package("requests", &config).unwrap();
+    assert_eq!(package.license("2.22.0"), Some("Apache-2.0"));
+    // Older releases may have been under another license, their own document tells
+    assert_eq!(package.license("2.21.0"), None);
+    assert_eq!(
+        store.get_license(&package, "requests", "2.21.0", &config),
+        Some("Apache-2.0".to_string())
+    );
+    assert_eq!(
+        store.get_license(&package, "requests", "1.0.0", &config),
+        None
+    );
+}
+
+#[test]
+fn cratesio_rust_version() {
+    let clap = fixture("registry/cratesio/clap.json");
+    let registry = MockRegistry::start(&[("/clap", 200, &clap)]);
+    let store = Cratesio::new(Registry::new(&registry.url));
+    let package = store.get_package("clap", &config()).unwrap();
+
+    // Stable versions only, yanked ones are not candidates
+    assert_eq!(
+        newest_compatible(&store, &package, "1.70"),
+        Some("4.4.18".to_string())
+    );
+    assert_eq!(
+        newest_compatible(&store, &package, "1.74.1"),
+        Some("4.5.4".to_string())
+    );
+    assert_eq!(
+        newest_compatible(&store, &package, "1.69"),
+        Some("4.3.24".to_string())
+    );
+    // Versions without a rust_version are assumed to build with anything
+    assert_eq!(
+        newest_compatible(&store, &package, "1.50"),
+        Some("3.2.25".to_string())
+    );
+    assert_eq!(
+        newest_compatible(&store, &package, "not a version"),
+        Some("4.5.4".to_string())
+    );
+}
+
+#[test]
+fn pypi_requires_python() {
+    let django = fixture("registry/pypi/django.json");
+    let registry = MockRegistry::start(&[("/Django/json", 200, &django)]);
+    let store = Pypi::new(Registry::new(&registry.url));
+    let package = store.get_package("Django", &config()).unwrap();
+
+    assert_eq!(
+        newest_compatible(&store, &package, "3.12"),
+        Some("5.0.3".to_string())
+    );
+    assert_eq!(
+        newest_compatible(&store, &package, "3.8"),
+        Some("4.2.11".to_string())
+    );
+    // Versions are compared as PEP 440 ones, 2.2 has no requirement
+    assert_eq!(
+        newest_compatible(&store, &package, "3.5"),
+        Some("2.2".to_string())
+    );
+    assert_eq!(package.license("5.0.3"), Some("BSD-3-Clause"));
+    assert_eq!(package.license("4.2.11"), None);
+}
+
+#[test]
+fn pipfile_two_part_versions() {
+    let pytz = fixture("registry/pypi/pytz.json");
+    let registry = MockRegistry::start(&[("/pytz/json", 200, &pytz)]);
+    let store = Pypi::new(Registry::new(&registry.url));
+    let manifest = fixture("parser/pipfile_versions/Pipfile");
+    let dependencies = PipfileParser::get_dependencies(
+        &manifest,
+        &fixture("parser/pipfile_versions/Pipfile.lock"),
+    )
+    .unwrap();
+    assert_eq!(dependencies[0].current, "2023.3");
+    let package = store.get_package("pytz", &config()).unwrap();
+
+    // Not semver, but still an update, and post releases are behind too
+    let (latest, update) = check_dependency(&store, &dependencies[0], Ok(&package), &config());
+    let update = update.unwrap();
+    assert!(update.kind == UpdateKind::Major);
+    assert_eq!(update.version, "2024.1");
+    let behind = update.behind;
+    assert_eq!((behind.major, behind.minor, behind.patch), (1, 1, 1));
+    assert_eq!(
+        latest,
+        vec![(" -> 2024.1".to_string(), consts::MAJOR_HG.to_string())]
+    );
+
+    let toolchain = PipfileParser::get_toolchain(&manifest).unwrap();
+    assert_eq!(
+        newest_compatible(&store, &package, &toolchain),
+        Some("2023.4".to_string())
+    );
+}
+
+#[test]
+fn npm_engines() {
+    let eslint = fixture("registry/npm/eslint.json");
+    let registry = MockRegistry::start(&[("/eslint", 200, &eslint)]);
+    let store = Npm::new(Registry::new(&registry.url));
+    let package = store.get_package("eslint", &config()).unwrap();
+
+    // The oldest node allowed by the manifest's engines must be supported
+    assert_eq!(
+        newest_compatible(&store, &package, ">=20.10.0"),
+        Some("9.0.0".to_string())
+    );
+    assert_eq!(
+        newest_compatible(&store, &package, ">= 16"),
+        Some("8.57.0".to_string())
+    );
+    assert_eq!(
+        newest_compatible(&store, &package, "^14.17.0 || >=16"),
+        Some("8.57.0".to_string())
+    );
+    assert_eq!(
+        newest_compatible(&store, &package, "^10.13.0"),
+        Some("7.32.0".to_string())
+    );
+    assert_eq!(newest_compatible(&store, &package, "11.x"), None);
+    assert_eq!(
+        newest_compatible(&store, &package, "18.0.0 - 20.0.0"),
+        Some("8.57.0".to_string())
+    );
+    // No lower bound, nothing to check
+    assert_eq!(
+        newest_compatible(&store, &package, "<22"),
+        Some("9.0.0".to_string())
+    );
+}
+
+#[test]
+fn timestamps() {
+    assert_eq!(timestamp("1970-01-01T00:00:00Z"), Some(0));
+    assert_eq!(timestamp("2019-05-28T18:19:17.231Z"), Some(1_559_067_557));
+    assert_eq!(timestamp("2019-05-16T15:22:39"), Some(1_558_020_159));
+    assert_eq!(timestamp("2000-02-29T23:59:59"), Some(951_868_799));
+    assert_eq!(
+        timestamp("2019-06-02T21:27:11.345131+02:00"),
+        Some(1_559_503_631)
+    );
+    assert_eq!(timestamp("2019-06-02"), Some(1_559_433_600));
+    assert_eq!(timestamp("yesterday"), None);
+    assert_eq!(timestamp("1969-12-31T00:00:00Z"), None);
+}
+
+#[test]
+fn release_dates() {
+    let serde = fixture("registry/cratesio/serde.json");
+    let react = fixture("registry/npm/react.json");
+    let types_node = fixture("registry/npm/types-node.json");
+    let requests = fixture("registry/pypi/requests.json");
+    let registry = MockRegistry::start(&[
+        ("/serde", 200, &serde),
+        ("/react", 200, &react),
+        ("/@types/node", 200, &types_node),
+        ("/requests/json", 200, &requests),
+    ]);
+
+    let package = Cratesio::new(Registry::new(&registry.url))
+        .get_package("serde", &config())
+        .unwrap();
+    assert_eq!(package.released.get("1.0.92"), Some(&1_559_510_831));
+    assert_eq!(package.last_release, Some(1_559_510_831));
+
+    let store = Npm::new(Registry::new(&registry.url));
+    let package = store.get_package("react", &config()).unwrap();
+    assert_eq!(package.released.get("16.8.6"), Some(&1_553_749_562));
+    assert!(!package.released.contains_key("modified"));
+    // Pre-releases are releases too
+    assert_eq!(package.last_release, Some(1_558_976_713));
+    // Abbreviated metadata only has the modification date
+    let package = store.get_package("@types/node", &config()).unwrap();
+    assert!(package.released.is_empty());
+    assert_eq!(package.last_release, Some(1_559_260_491));
+
+    // The first file uploaded
+    let package = Pypi::new(Registry::new(&registry.url))
+        .get_package("requests", &config())
+        .unwrap();
+    assert_eq!(package.released.get("2.21.0"), Some(&1_544_456_446));
+    assert_eq!(package.last_release, Some(1_558_020_159));
+}
+
+#[test]
+fn releases_behind() {
+    let serde = fixture("registry/cratesio/serde.json");
+    let react = fixture("registry/npm/react.json");
+    let registry = MockRegistry::start(&[("/serde", 200, &serde), ("/react", 200, &react)]);
+
+    let store = Cratesio::new(Registry::new(&registry.url));
+    let package = store.get_package("serde", &config()).unwrap();
+    let (_, update) = check_dependency(
+        &store,
+        &dependency("serde", "0.9.15"),
+        Ok(&package),
+        &config(),
+    );
+    // The yanked version doesn't count
+    let behind = update.unwrap().behind;
+    assert_eq!((behind.major, behind.minor, behind.patch), (2, 0, 0));
+    assert_eq!(behind.since.map(date), Some("2019-05-13".to_string()));
+
+    // Pre-releases don't count either
+    let store = Npm::new(Registry::new(&registry.url));
+    let package = store.get_package("react", &config()).unwrap();
+    let (_, update) = check_dependency(
+        &store,
+        &dependency("react", "16.8.5"),
+        Ok(&package),
+        &config(),
+    );
+    let behind = update.unwrap().behind;
+    assert_eq!((behind.major, behind.minor, behind.patch), (0, 0, 1));
+    assert_eq!(behind.total(), 1);
+}
+
+#[test]
+fn dates() {
+    assert_eq!(date(0), "1970-01-01");
+    assert_eq!(date(951_868_799), "2000-02-29");
+    assert_eq!(date(951_868_800), "2000-03-01");
+    assert_eq!(date(1_559_510_831), "2019-06-02");
+    assert_eq!(date(4_107_542_400), "2100-03-01");
+}
+
+#[test]
+fn repositories_and_tarballs() {
+    let serde = fixture("registry/cratesio/serde.json");
+    let react = fixture("registry/npm/react.json");
+    let requests = fixture("registry/pypi/requests.json");
+    let registry = MockRegistry::start(&[
+        ("/serde", 200, &serde),
+        ("/serde/1.0.92/download", 200, "serde archive"),
+        ("/react", 200, &react),
+        ("/@types/node/-/node-12.0.4.tgz", 200, "node archive"),
+        ("/requests/json", 200, &requests),
+    ]);
+
+    let cratesio = Cratesio::new(Registry::new(&registry.url));
+    let package = cratesio.get_package("serde", &config()).unwrap();
+    assert_eq!(
+        package.repository.as_deref(),
+        Some("https://github.com/serde-rs/serde")
+    );
+    let tarball = cratesio.get_tarball("serde", "1.0.92", &config()).unwrap();
+    assert_eq!(tarball.as_deref(), Some(&b"serde archive"[..]));
+    assert!(cratesio.get_tarball("serde", "0.1.0", &config()).is_err());
+
+    let npm = Npm::new(Registry::new(&registry.url));
+    let package = npm.get_package("react", &config()).unwrap();
+    assert_eq!(
+        package.repository.as_deref(),
+        Some("https://github.com/facebook/react")
+    );
+    // Scoped packages keep the scope out of the archive name
+    let tarball = npm.get_tarball("@types/node", "12.0.4", &config()).unwrap();
+    assert_eq!(tarball.as_deref(), Some(&b"node archive"[..]));
+
+    // Source links are preferred over the home page, PyPI has no archive to look into
+    let pypi = Pypi::new(Registry::new(&registry.url));
+    let package = pypi.get_package("requests", &config()).unwrap();
+    assert_eq!(
+        package.repository.as_deref(),
+        Some("https://github.com/psf/requests")
+    );
+    assert!(pypi
+        .get_tarball("requests", "2.22.0", &config())
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn requirements_satisfied() {
+    let cratesio = Cratesio::new(Registry::new("http://localhost"));
+    assert_eq!(cratesio.satisfies("1.0", "1.0.92"), Some(true));
+    assert_eq!(cratesio.satisfies("~1.0.90", "1.1.0"), Some(false));
+    assert_eq!(cratesio.satisfies("1.0", "not a version"), None);
+
+    // npm ranges, with alternatives and hyphens
+    let npm = Npm::new(Registry::new("http://localhost"));
+    assert_eq!(npm.satisfies("^16.8.0", "16.8.6"), Some(true));
+    assert_eq!(npm.satisfies("^15.0.0 || ^16.0.0", "16.8.6"), Some(true));
+    assert_eq!(npm.satisfies("1.0.0 - 1.2.0", "1.3.0"), Some(false));
+
+    // PEP 440 specifiers, * allows anything
+    let pypi = Pypi::new(Registry::new("http://localhost"));
+    assert_eq!(pypi.satisfies(">=2.2", "2.1.0"), Some(false));
+    assert_eq!(pypi.satisfies("==2.22.0", "2.22"), Some(true));
+    assert_eq!(pypi.satisfies("*", "2.0.0rc1"), Some(true));
+}
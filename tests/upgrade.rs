@@ -0,0 +1,69 @@
+use neovim_package_info::upgrade::{upgrade_requirement, Level};
+
+fn versions(versions: &[&str]) -> Vec<String> {
+    versions.iter().map(|version| version.to_string()).collect()
+}
+
+#[test]
+fn levels_and_operators() {
+    let available = versions(&["1.0.0", "1.0.5", "1.4.2", "2.0.0", "2.1.0-beta.1"]);
+    let upgrade = |requirement, level| upgrade_requirement(requirement, "1.0.0", &available, level);
+
+    assert_eq!(upgrade("1.0.0", Level::Patch), Some("1.0.5".to_string()));
+    assert_eq!(upgrade("^1.0.0", Level::Minor), Some("^1.4.2".to_string()));
+    assert_eq!(upgrade("~1.0", Level::Minor), Some("~1.4.2".to_string()));
+    // Pre-releases are never upgrade candidates
+    assert_eq!(
+        upgrade(">=1.0.0", Level::Major),
+        Some(">=2.0.0".to_string())
+    );
+    // Ranges can't be rewritten to a single version
+    assert_eq!(upgrade("<2.0.0", Level::Major), None);
+    assert_eq!(upgrade("1.0.0 - 2.0.0", Level::Major), None);
+}
+
+#[test]
+fn from_the_locked_version() {
+    let available = versions(&["1.0.0", "1.2.0", "1.3.0"]);
+
+    assert_eq!(
+        upgrade_requirement("1.0", "1.2.0", &available, Level::Minor),
+        Some("1.3.0".to_string())
+    );
+    assert_eq!(
+        upgrade_requirement("1.0", "1.3.0", &available, Level::Minor),
+        None
+    );
+}
+
+#[test]
+fn zero_major_versions() {
+    let available = versions(&["0.2.3", "0.2.9", "0.3.0", "1.0.0"]);
+    let upgrade = |level| upgrade_requirement("0.2.3", "0.2.3", &available, level);
+
+    // The minor number of a 0.x version is a breaking change
+    assert_eq!(upgrade(Level::Patch), Some("0.2.9".to_string()));
+    assert_eq!(upgrade(Level::Minor), Some("0.2.9".to_string()));
+    assert_eq!(upgrade(Level::Major), Some("1.0.0".to_string()));
+
+    // And so is the patch number of a 0.0.x one
+    let available = versions(&["0.0.3", "0.0.4", "0.1.0"]);
+    let upgrade = |level| upgrade_requirement("0.0.3", "0.0.3", &available, level);
+    assert_eq!(upgrade(Level::Patch), None);
+    assert_eq!(upgrade(Level::Minor), None);
+    assert_eq!(upgrade(Level::Major), Some("0.1.0".to_string()));
+}
+
+#[test]
+fn pypi_versions() {
+    let available = versions(&["2.21", "2.22.0", "2.22.1", "3.0"]);
+    let upgrade = |requirement, level| upgrade_requirement(requirement, "", &available, level);
+
+    assert_eq!(upgrade("==2.21", Level::Patch), None);
+    assert_eq!(
+        upgrade("==2.21", Level::Minor),
+        Some("==2.22.1".to_string())
+    );
+    assert_eq!(upgrade("~=2.21", Level::Major), Some("~=3.0.0".to_string()));
+    assert_eq!(upgrade(">=2.21,<3", Level::Minor), None);
+}